@@ -0,0 +1,72 @@
+#[cfg(target_os = "linux")]
+use nix::sched::{sched_getaffinity, sched_setaffinity, CpuSet};
+#[cfg(target_os = "linux")]
+use nix::unistd::Pid;
+
+// Pins the calling thread to the given CPUs until dropped, forked children
+// inherit the affinity
+pub struct AffinityGuard {
+    #[cfg(target_os = "linux")]
+    previous: Option<CpuSet>,
+}
+
+impl AffinityGuard {
+    #[cfg(target_os = "linux")]
+    pub fn pin(cpus: &[usize]) -> Self {
+        if cpus.is_empty() {
+            return Self { previous: None };
+        }
+        let previous = match sched_getaffinity(Pid::from_raw(0)) {
+            Ok(previous) => previous,
+            Err(err) => {
+                eprintln!("Не удалось получить привязку к процессорам: {}", err);
+                return Self { previous: None };
+            }
+        };
+        let mut cpu_set = CpuSet::new();
+        for cpu in cpus {
+            if let Err(err) = cpu_set.set(*cpu) {
+                eprintln!("Некорректный номер процессора {}: {}", cpu, err);
+                return Self { previous: None };
+            }
+        }
+        if let Err(err) = sched_setaffinity(Pid::from_raw(0), &cpu_set) {
+            eprintln!(
+                "Не удалось привязать поток к процессорам {:?}: {}",
+                cpus, err
+            );
+            return Self { previous: None };
+        }
+        Self {
+            previous: Some(previous),
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn pin(cpus: &[usize]) -> Self {
+        if !cpus.is_empty() {
+            eprintln!("Привязка к процессорам не поддерживается на этой платформе");
+        }
+        Self {}
+    }
+
+    // Whether the mask has been applied
+    #[cfg(target_os = "linux")]
+    pub fn is_pinned(&self) -> bool {
+        self.previous.is_some()
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn is_pinned(&self) -> bool {
+        false
+    }
+}
+
+impl Drop for AffinityGuard {
+    fn drop(&mut self) {
+        #[cfg(target_os = "linux")]
+        if let Some(previous) = self.previous.take() {
+            _ = sched_setaffinity(Pid::from_raw(0), &previous);
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use crate::measures::{
+    ExecutionOrder, ProbeOutcome, SamplingMode, SizeMeasurement, ThresholdSearch, WarmUp,
+};
+
+use anyhow::Result;
+use fs_err as fs;
+
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+// Settings the checkpointed measurements depend on. A checkpoint is resumed
+// only by a pack with the same settings
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CheckpointPack {
+    pub filename: String,
+    pub sizes: Vec<String>,
+    pub algorithms: Vec<String>,
+    pub timer: String,
+    pub iterations_amount: u64,
+    pub min_batch_time: Option<Duration>,
+    pub use_threshold: bool,
+    pub threshold: Duration,
+    pub memory_threshold: Option<u64>,
+    pub threshold_search: ThresholdSearch,
+    pub warm_up: WarmUp,
+    pub sampling_mode: SamplingMode,
+    pub process_isolation: bool,
+    pub execution_order: ExecutionOrder,
+    pub cpu_affinity: Vec<usize>,
+    pub shared_generator: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub enum CheckpointEntry {
+    Pack(CheckpointPack),
+    // Maximal size and iterations amounts found before the measurements
+    Algorithm {
+        filename: String,
+        max_size_number: usize,
+        probe_outcomes: Vec<Option<ProbeOutcome>>,
+        iterations_amounts: Vec<u64>,
+    },
+    Measurement {
+        filename: String,
+        measurement: u64,
+        size_index: usize,
+        samples: SizeMeasurement,
+    },
+}
+
+// Entries of the checkpoint, if it was made by a pack with the same settings.
+// A line cut by the interruption is skipped
+pub fn read(path: &Path, pack: &CheckpointPack) -> Result<Vec<CheckpointEntry>> {
+    if !path.is_file() {
+        return Ok(vec![]);
+    }
+    let data = fs::read_to_string(path)?;
+    let mut entries = data
+        .lines()
+        .map_while(|line| serde_json::from_str::<CheckpointEntry>(line).ok());
+    match entries.next() {
+        Some(CheckpointEntry::Pack(checkpoint_pack)) if checkpoint_pack == *pack => {
+            Ok(entries.collect())
+        }
+        _ => Ok(vec![]),
+    }
+}
+
+// Append-only file, every entry is a separate json line
+pub struct CheckpointWriter {
+    file: fs::File,
+}
+
+impl CheckpointWriter {
+    pub fn create(path: &Path, pack: &CheckpointPack) -> Result<Self> {
+        if let Some(dir_path) = path.parent() {
+            fs::create_dir_all(dir_path)?;
+        }
+        let mut writer = Self {
+            file: fs::File::create(path)?,
+        };
+        writer.append(&CheckpointEntry::Pack(pack.clone()))?;
+        Ok(writer)
+    }
+
+    pub fn append(&mut self, entry: &CheckpointEntry) -> Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes())?;
+        self.file.flush()?;
+        Ok(())
+    }
+}
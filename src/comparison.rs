@@ -0,0 +1,236 @@
+use crate::description;
+use crate::graph::fileio::{get_filename, read_samples};
+use crate::graph::quartiles::Quartiles;
+use crate::statistics;
+use crate::units::TimeUnit;
+
+use anyhow::Result;
+use fs_err as fs;
+use indexmap::IndexMap;
+
+use std::fmt;
+use std::path::Path;
+
+pub const DEFAULT_SIGNIFICANCE_LEVEL: f64 = 0.05;
+const DESCRIPTION_FILE: &str = "description.json";
+
+/// Samples of a pack: algorithm filename -> size -> samples
+pub type PackSamples = IndexMap<String, IndexMap<String, Vec<f64>>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Faster,
+    Slower,
+    NoChange,
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let output = match self {
+            Self::Faster => "быстрее",
+            Self::Slower => "медленнее",
+            Self::NoChange => "без изменений",
+        };
+        write!(f, "{}", output)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SizeComparison {
+    pub size: String,
+    pub baseline_median: f64,
+    pub current_median: f64,
+    pub p_value: f64,
+    pub verdict: Verdict,
+}
+
+impl SizeComparison {
+    pub fn relative_change(&self) -> f64 {
+        (self.current_median - self.baseline_median) / self.baseline_median
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AlgorithmComparison {
+    pub filename: String,
+    pub sizes: Vec<SizeComparison>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PackComparison {
+    pub significance_level: f64,
+    pub algorithms: Vec<AlgorithmComparison>,
+}
+
+// Samples are converted to nanoseconds. The unit is taken from the pack
+// description next to the data directory, baselines are always in nanoseconds
+pub fn read_pack_samples(data_path: &Path) -> Result<PackSamples> {
+    let stored_unit = match data_path.parent().map(|x| x.join(DESCRIPTION_FILE)) {
+        Some(description_path) if description_path.is_file() => {
+            description::read_time_unit(&description_path)?
+        }
+        _ => TimeUnit::Nanoseconds,
+    };
+    let mut pack_samples = PackSamples::new();
+    for algorithm_dir_entry in fs::read_dir(data_path)? {
+        let algorithm_path = algorithm_dir_entry?.path();
+        if !algorithm_path.is_dir() {
+            continue;
+        }
+        let algorithm_name = get_filename(&algorithm_path)?.to_string();
+
+        let mut size_samples = vec![];
+        for size_dir_entry in fs::read_dir(&algorithm_path)? {
+            let size_path = size_dir_entry?.path();
+            let size = match size_path.file_stem().and_then(|x| x.to_str()) {
+                Some(size) => size.to_string(),
+                None => continue,
+            };
+            let samples = read_samples(&size_path)?
+                .into_iter()
+                .map(|x| stored_unit.convert(x, TimeUnit::Nanoseconds))
+                .collect::<Vec<f64>>();
+            size_samples.push((size, samples));
+        }
+        size_samples.sort_by(|a, b| match (a.0.parse::<f64>(), b.0.parse::<f64>()) {
+            (Ok(a), Ok(b)) => a.total_cmp(&b),
+            _ => a.0.cmp(&b.0),
+        });
+
+        pack_samples.insert(algorithm_name, size_samples.into_iter().collect());
+    }
+    pack_samples.sort_keys();
+    Ok(pack_samples)
+}
+
+pub fn compare_samples(
+    baseline: &PackSamples,
+    current: &PackSamples,
+    significance_level: f64,
+) -> PackComparison {
+    let mut algorithms = vec![];
+    for (filename, current_sizes) in current.iter() {
+        let baseline_sizes = match baseline.get(filename) {
+            Some(sizes) => sizes,
+            None => continue,
+        };
+        let mut sizes = vec![];
+        for (size, current_samples) in current_sizes.iter() {
+            let baseline_samples = match baseline_sizes.get(size) {
+                Some(samples) => samples,
+                None => continue,
+            };
+            if baseline_samples.is_empty() || current_samples.is_empty() {
+                continue;
+            }
+            let baseline_median = Quartiles::new(baseline_samples).median();
+            let current_median = Quartiles::new(current_samples).median();
+            let p_value = statistics::mann_whitney_u_test(baseline_samples, current_samples);
+            let verdict = if p_value >= significance_level {
+                Verdict::NoChange
+            } else if current_median < baseline_median {
+                Verdict::Faster
+            } else {
+                Verdict::Slower
+            };
+            sizes.push(SizeComparison {
+                size: size.clone(),
+                baseline_median,
+                current_median,
+                p_value,
+                verdict,
+            });
+        }
+        algorithms.push(AlgorithmComparison {
+            filename: filename.clone(),
+            sizes,
+        });
+    }
+    PackComparison {
+        significance_level,
+        algorithms,
+    }
+}
+
+pub fn compare_data(
+    baseline_data_path: &Path,
+    current_data_path: &Path,
+    significance_level: f64,
+) -> Result<PackComparison> {
+    let baseline = read_pack_samples(baseline_data_path)?;
+    let current = read_pack_samples(current_data_path)?;
+    Ok(compare_samples(&baseline, &current, significance_level))
+}
+
+impl PackComparison {
+    pub fn print(&self) {
+        use prettytable::{Cell, Row, Table};
+        let mut table = Table::new();
+        table.add_row(Row::new(vec![
+            Cell::new("Алгоритм"),
+            Cell::new("Размер"),
+            Cell::new("Базовая медиана, нс"),
+            Cell::new("Текущая медиана, нс"),
+            Cell::new("Изменение"),
+            Cell::new("p"),
+            Cell::new("Вердикт"),
+        ]));
+        for algorithm in self.algorithms.iter() {
+            for size in algorithm.sizes.iter() {
+                table.add_row(Row::new(vec![
+                    Cell::new(&algorithm.filename),
+                    Cell::new(&size.size),
+                    Cell::new(&format!("{:.0}", size.baseline_median)),
+                    Cell::new(&format!("{:.0}", size.current_median)),
+                    Cell::new(&format!("{:+.2}%", size.relative_change() * 100.0)),
+                    Cell::new(&format!("{:.4}", size.p_value)),
+                    Cell::new(&size.verdict.to_string()),
+                ]));
+            }
+        }
+
+        println!("Уровень значимости: {}", self.significance_level);
+        table.printstd();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pack(samples: &[f64]) -> PackSamples {
+        let mut sizes = IndexMap::new();
+        sizes.insert(String::from("100"), samples.to_vec());
+        let mut pack = PackSamples::new();
+        pack.insert(String::from("algorithm"), sizes);
+        pack
+    }
+
+    fn verdict(baseline: &[f64], current: &[f64]) -> Verdict {
+        let comparison =
+            compare_samples(&pack(baseline), &pack(current), DEFAULT_SIGNIFICANCE_LEVEL);
+        comparison.algorithms[0].sizes[0].verdict
+    }
+
+    #[test]
+    fn verdicts() {
+        let slow = [6.0, 7.0, 8.0, 9.0, 10.0];
+        let fast = [1.0, 2.0, 3.0, 4.0, 5.0];
+        assert_eq!(verdict(&slow, &fast), Verdict::Faster);
+        assert_eq!(verdict(&fast, &slow), Verdict::Slower);
+        assert_eq!(verdict(&fast, &fast), Verdict::NoChange);
+        assert_eq!(
+            verdict(&fast, &[1.5, 2.5, 3.5, 4.5, 5.5]),
+            Verdict::NoChange
+        );
+    }
+
+    #[test]
+    fn missing_sizes_are_skipped() {
+        let mut current = pack(&[1.0, 2.0]);
+        current[0].insert(String::from("200"), vec![1.0]);
+        let comparison = compare_samples(&pack(&[1.0, 2.0]), &current, DEFAULT_SIGNIFICANCE_LEVEL);
+        assert_eq!(comparison.algorithms[0].sizes.len(), 1);
+        assert!((comparison.algorithms[0].sizes[0].p_value - 1.0).abs() < 1e-6);
+    }
+}
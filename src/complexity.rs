@@ -0,0 +1,194 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ComplexityModel {
+    Constant,
+    Logarithmic,
+    Linear,
+    Linearithmic,
+    Quadratic,
+    Cubic,
+    Exponential,
+}
+
+impl ComplexityModel {
+    // Ordered from the simplest model to the most complex one: on equal R²
+    // the simpler model wins
+    pub const ALL: [ComplexityModel; 7] = [
+        ComplexityModel::Constant,
+        ComplexityModel::Logarithmic,
+        ComplexityModel::Linear,
+        ComplexityModel::Linearithmic,
+        ComplexityModel::Quadratic,
+        ComplexityModel::Cubic,
+        ComplexityModel::Exponential,
+    ];
+
+    pub fn value(&self, n: f64) -> f64 {
+        match self {
+            Self::Constant => 1.0,
+            Self::Logarithmic => n.log2(),
+            Self::Linear => n,
+            Self::Linearithmic => n * n.log2(),
+            Self::Quadratic => n * n,
+            Self::Cubic => n * n * n,
+            Self::Exponential => n.exp2(),
+        }
+    }
+}
+
+impl fmt::Display for ComplexityModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let output = match self {
+            Self::Constant => "O(1)",
+            Self::Logarithmic => "O(log n)",
+            Self::Linear => "O(n)",
+            Self::Linearithmic => "O(n log n)",
+            Self::Quadratic => "O(n²)",
+            Self::Cubic => "O(n³)",
+            Self::Exponential => "O(2ⁿ)",
+        };
+        write!(f, "{}", output)
+    }
+}
+
+/// Model `y = intercept + coefficient * f(n)` fitted by least squares, the
+/// intercept takes the fixed overhead of a call
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ComplexityFit {
+    pub model: ComplexityModel,
+    #[serde(default)]
+    pub intercept: f64,
+    pub coefficient: f64,
+    pub r_squared: f64,
+}
+
+impl fmt::Display for ComplexityFit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}, a = {:.3e}, c = {:.3e}, R² = {:.4}",
+            self.model, self.intercept, self.coefficient, self.r_squared
+        )
+    }
+}
+
+// Growing models with a negative coefficient do not describe the values
+pub fn fit(sizes: &[f64], values: &[f64], model: ComplexityModel) -> Option<ComplexityFit> {
+    if sizes.len() != values.len() || sizes.len() < 2 {
+        return None;
+    }
+    let model_values = sizes.iter().map(|n| model.value(*n)).collect::<Vec<f64>>();
+    if model_values.iter().any(|x| !x.is_finite()) {
+        return None;
+    }
+
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let (intercept, coefficient) = if model == ComplexityModel::Constant {
+        (0.0, mean)
+    } else {
+        let model_mean = model_values.iter().sum::<f64>() / model_values.len() as f64;
+        let model_squares_sum = model_values
+            .iter()
+            .map(|x| (x - model_mean).powi(2))
+            .sum::<f64>();
+        if model_squares_sum == 0.0 || !model_squares_sum.is_finite() {
+            return None;
+        }
+        let products_sum = model_values
+            .iter()
+            .zip(values)
+            .map(|(x, y)| (x - model_mean) * (y - mean))
+            .sum::<f64>();
+        let coefficient = products_sum / model_squares_sum;
+        if coefficient < 0.0 {
+            return None;
+        }
+        (mean - coefficient * model_mean, coefficient)
+    };
+
+    let total_sum_of_squares = values.iter().map(|y| (y - mean).powi(2)).sum::<f64>();
+    let residual_sum_of_squares = model_values
+        .iter()
+        .zip(values)
+        .map(|(x, y)| (y - intercept - coefficient * x).powi(2))
+        .sum::<f64>();
+    let r_squared = if total_sum_of_squares > 0.0 {
+        1.0 - residual_sum_of_squares / total_sum_of_squares
+    } else if residual_sum_of_squares == 0.0 {
+        1.0
+    } else {
+        0.0
+    };
+
+    Some(ComplexityFit {
+        model,
+        intercept,
+        coefficient,
+        r_squared,
+    })
+}
+
+/// Fits of every model from `ComplexityModel::ALL`, best fit first
+pub fn fit_all(sizes: &[f64], values: &[f64]) -> Vec<ComplexityFit> {
+    let mut fits = ComplexityModel::ALL
+        .iter()
+        .filter_map(|model| fit(sizes, values, *model))
+        .collect::<Vec<ComplexityFit>>();
+    // R² adjusted for the intercept keeps noise from beating the constant
+    // model. Stable sort keeps simpler models first among equal fits
+    let adjusted_r_squared = |fit: &ComplexityFit| {
+        let parameters = if fit.model == ComplexityModel::Constant {
+            1
+        } else {
+            2
+        };
+        if sizes.len() > parameters {
+            1.0 - (1.0 - fit.r_squared) * (sizes.len() - 1) as f64
+                / (sizes.len() - parameters) as f64
+        } else {
+            fit.r_squared
+        }
+    };
+    fits.sort_by(|a, b| adjusted_r_squared(b).total_cmp(&adjusted_r_squared(a)));
+    fits
+}
+
+pub fn best_fit(sizes: &[f64], values: &[f64]) -> Option<ComplexityFit> {
+    fit_all(sizes, values).into_iter().next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn linear_with_overhead_is_linear() {
+        let sizes = (1..=20).map(|i| (i * 1000) as f64).collect::<Vec<f64>>();
+        let values = sizes
+            .iter()
+            .map(|n| 2.0 * n + 100000.0)
+            .collect::<Vec<f64>>();
+        let fit = best_fit(&sizes, &values).unwrap();
+        assert_eq!(fit.model, ComplexityModel::Linear);
+        assert!((fit.intercept - 100000.0).abs() < 1e-3);
+        assert!((fit.coefficient - 2.0).abs() < 1e-9);
+        assert!(fit.r_squared > 0.999999);
+    }
+
+    #[test]
+    fn equal_values_are_constant() {
+        let sizes = [10.0, 100.0, 1000.0];
+        let fit = best_fit(&sizes, &[5.0, 5.0, 5.0]).unwrap();
+        assert_eq!(fit.model, ComplexityModel::Constant);
+        assert_eq!(fit.coefficient, 5.0);
+    }
+
+    #[test]
+    fn noise_is_constant() {
+        let sizes = (1..=8).map(|i| (i * 100) as f64).collect::<Vec<f64>>();
+        let values = [5.0, 6.0, 5.0, 6.0, 6.0, 5.0, 6.0, 5.0];
+        let fit = best_fit(&sizes, &values).unwrap();
+        assert_eq!(fit.model, ComplexityModel::Constant);
+    }
+}
@@ -3,6 +3,12 @@ use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::time::Duration;
 
+use crate::environment::Environment;
+use crate::measures::{ExecutionOrder, ProbeOutcome, SamplingMode, ThresholdSearch, WarmUp};
+use crate::sizes::SizesSpec;
+use crate::statistics::OutlierPolicy;
+use crate::units::TimeUnit;
+
 use anyhow::Result;
 
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -10,6 +16,10 @@ pub struct TargetDescription {
     pub filename: String,
     pub description: String,
     pub max_size_number: usize,
+    #[serde(default)]
+    pub iterations_amounts: Vec<u64>,
+    #[serde(default)]
+    pub probe_outcomes: Vec<Option<ProbeOutcome>>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -22,8 +32,39 @@ where
     pub sizes: Vec<GenArgT>,
     pub x_label: String,
     pub y_label: String,
+    // Label of the second size of a grid
+    #[serde(default)]
+    pub second_size_label: Option<String>,
+    #[serde(default)]
+    pub timer: Option<String>,
+    #[serde(default = "TimeUnit::legacy")]
+    pub time_unit: TimeUnit,
     pub iterations_amount: u64,
+    #[serde(default)]
+    pub min_batch_time: Option<Duration>,
     pub threshold: Duration,
+    #[serde(default)]
+    pub memory_threshold: Option<u64>,
+    #[serde(default)]
+    pub threshold_search: ThresholdSearch,
+    #[serde(default)]
+    pub outlier_policy: OutlierPolicy,
+    #[serde(default)]
+    pub warm_up: WarmUp,
+    #[serde(default)]
+    pub sampling_mode: SamplingMode,
+    #[serde(default)]
+    pub process_isolation: bool,
+    #[serde(default)]
+    pub execution_order: ExecutionOrder,
+    #[serde(default)]
+    pub shared_generator: bool,
+    #[serde(default)]
+    pub cpu_affinity: Vec<usize>,
+    #[serde(default)]
+    pub sizes_spec: Option<SizesSpec>,
+    #[serde(default)]
+    pub environment: Option<Environment>,
     pub target_descriptions: Vec<TargetDescription>,
 }
 
@@ -75,3 +116,16 @@ where
         Ok(description)
     }
 }
+
+#[derive(serde::Deserialize)]
+struct TimeUnitDescription {
+    #[serde(default = "TimeUnit::legacy")]
+    time_unit: TimeUnit,
+}
+
+// Unit of the samples of a pack, read without knowing the type of its sizes
+pub fn read_time_unit(path: &Path) -> Result<TimeUnit> {
+    let data = fs::read_to_string(path)?;
+    let description: TimeUnitDescription = serde_json::from_str(&data)?;
+    Ok(description.time_unit)
+}
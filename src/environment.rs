@@ -0,0 +1,71 @@
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const CPUINFO_FILE: &str = "/proc/cpuinfo";
+const OSRELEASE_FILE: &str = "/proc/sys/kernel/osrelease";
+const LOADAVG_FILE: &str = "/proc/loadavg";
+const GOVERNOR_FILE: &str = "/sys/devices/system/cpu/cpu0/cpufreq/scaling_governor";
+
+// Host the measurements were made on. Fields that can not be read are left empty
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct Environment {
+    pub cpu_model: Option<String>,
+    pub cpu_cores: Option<usize>,
+    pub kernel_version: Option<String>,
+    pub load_average_start: Option<[f64; 3]>,
+    pub load_average_end: Option<[f64; 3]>,
+    pub cpu_governor: Option<String>,
+    pub crate_version: String,
+    pub build_profile: String,
+    // Seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+impl Environment {
+    pub fn capture(load_average_start: Option<[f64; 3]>) -> Self {
+        let cpuinfo = fs::read_to_string(CPUINFO_FILE).ok();
+        let cpu_model = cpuinfo.as_ref().and_then(|cpuinfo| {
+            cpuinfo
+                .lines()
+                .find(|line| line.starts_with("model name"))
+                .and_then(|line| line.split_once(':'))
+                .map(|(_, model)| model.trim().to_string())
+        });
+        let cpu_cores = cpuinfo.as_ref().map(|cpuinfo| {
+            cpuinfo
+                .lines()
+                .filter(|line| line.starts_with("processor"))
+                .count()
+        });
+        let build_profile = if cfg!(debug_assertions) {
+            "debug"
+        } else {
+            "release"
+        };
+        Self {
+            cpu_model,
+            cpu_cores,
+            kernel_version: read_trimmed(OSRELEASE_FILE),
+            load_average_start,
+            load_average_end: load_average(),
+            cpu_governor: read_trimmed(GOVERNOR_FILE),
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            build_profile: build_profile.to_string(),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|x| x.as_secs())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+// 1, 5 and 15 minutes load averages
+pub fn load_average() -> Option<[f64; 3]> {
+    let loadavg = fs::read_to_string(LOADAVG_FILE).ok()?;
+    let mut values = loadavg.split_whitespace().map(|x| x.parse::<f64>().ok());
+    Some([values.next()??, values.next()??, values.next()??])
+}
+
+fn read_trimmed(path: &str) -> Option<String> {
+    fs::read_to_string(path).ok().map(|x| x.trim().to_string())
+}
@@ -1,9 +1,14 @@
+use crate::metrics::Metric;
+use crate::units::TimeUnit;
+
 use anyhow::Result;
 use fs_err as fs;
 
 use std::fmt;
 use std::path::Path;
 
+// Variant names are the values of "output_type" in graph.conf
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, serde::Deserialize)]
 pub enum GraphOutputType {
     NONE,
@@ -14,17 +19,38 @@ pub enum GraphOutputType {
 
 impl fmt::Display for GraphOutputType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let output: &str;
-        match self {
-            Self::NONE => output = "",
-            Self::SVG => output = "svg",
-            Self::PDF => output = "pdf",
-            Self::PNG => output = "png",
-        }
+        let output = match self {
+            Self::NONE => "",
+            Self::SVG => "svg",
+            Self::PDF => "pdf",
+            Self::PNG => "png",
+        };
         write!(f, "{}", output)
     }
 }
 
+// How the quartiles of the samples of every size are drawn
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+pub enum PlotStyle {
+    // Line through the medians
+    #[default]
+    Lines,
+    // Medians with Q1-Q3 error bars
+    ErrorBars,
+    // Boxes from Q1 to Q3 with min-max whiskers and the median
+    Candlesticks,
+    // Filled band from Q1 to Q3 under the median line
+    Bands,
+}
+
+// How the measurements over a grid of sizes are drawn
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+pub enum GridStyle {
+    #[default]
+    Surface,
+    Heatmap,
+}
+
 #[derive(Debug, serde::Deserialize)]
 pub struct GraphConfig {
     pub output_type: GraphOutputType,
@@ -37,6 +63,14 @@ pub struct GraphConfig {
     pub y_end: u32,
     pub y_scale: f64,
     pub log_y: bool,
+    #[serde(default = "TimeUnit::legacy")]
+    pub time_unit: TimeUnit,
+    #[serde(default)]
+    pub metric: Metric,
+    #[serde(default)]
+    pub plot_style: PlotStyle,
+    #[serde(default)]
+    pub grid_style: GridStyle,
 }
 
 impl GraphConfig {
@@ -6,7 +6,7 @@ use serde::ser::StdError;
 use csv::ReaderBuilder;
 use std::error;
 use std::fmt;
-use std::io::prelude::*;
+use std::io::{prelude::*, BufReader};
 use std::path::Path;
 
 #[derive(Debug, Clone)]
@@ -96,7 +96,7 @@ pub fn read_prompt(prompt: &str) -> String {
         .flush()
         .expect("Невозможно сбросить буфер потока вывода");
     let line = read_line();
-    println!("");
+    println!();
 
     line
 }
@@ -106,7 +106,7 @@ pub fn create_file_from_string(name: &str, data: &String) -> Result<()> {
         .write(true)
         .create(true)
         .truncate(true)
-        .open(&name)
+        .open(name)
         .expect("Не удалось создать файл");
 
     if let Err(e) = file_out.write_all(data.as_bytes()) {
@@ -161,4 +161,44 @@ pub fn recreate_dir_all(path: &std::path::Path) -> Result<()> {
     }
     fs::create_dir_all(path)?;
     Ok(())
-}
\ No newline at end of file
+}
+
+pub fn read_samples(path: &Path) -> Result<Vec<f64>> {
+    let file = fs::File::open(path)?;
+    let reader = BufReader::new(file);
+    let mut samples = vec![];
+    for line in reader.lines() {
+        for value in line?.split_whitespace() {
+            samples.push(value.parse::<f64>()?);
+        }
+    }
+    Ok(samples)
+}
+
+// Multiplies every sample of every algorithm in a pack data directory by `factor`
+pub fn rescale_samples(data_path: &Path, factor: f64) -> Result<()> {
+    for algorithm_dir_entry in fs::read_dir(data_path)? {
+        let algorithm_path = algorithm_dir_entry?.path();
+        if !algorithm_path.is_dir() {
+            continue;
+        }
+        for size_dir_entry in fs::read_dir(&algorithm_path)? {
+            let size_path = size_dir_entry?.path();
+            let data = fs::read_to_string(&size_path)?;
+            let mut rescaled = String::new();
+            for line in data.lines() {
+                let values = line
+                    .split_whitespace()
+                    .map(|x| {
+                        x.parse::<f64>()
+                            .map(|x| ((x * factor).round() as u64).to_string())
+                    })
+                    .collect::<Result<Vec<String>, _>>()?;
+                rescaled.push_str(&values.join(" "));
+                rescaled.push('\n');
+            }
+            fs::write(&size_path, rescaled)?;
+        }
+    }
+    Ok(())
+}
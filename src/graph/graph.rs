@@ -1,8 +1,10 @@
-use super::config::GraphConfig;
+use super::config::{GraphConfig, GridStyle, PlotStyle};
 use super::fileio::create_file_from_string;
 use super::fileio::recreate_dir_all;
 use super::preprocess;
 use crate::description::PackMeasuresDescription;
+use crate::metrics::Metric;
+use crate::units::TimeUnit;
 
 use anyhow::Result;
 use fs_err as fs;
@@ -13,6 +15,7 @@ use std::str::FromStr;
 
 const PACKS_DIR: &str = "packs";
 const DATA_DIR: &str = "data";
+const METRICS_DIR: &str = "metrics";
 const TEMP_DIR: &str = "graph_temp";
 const CSV_DIR: &str = "csv";
 const PREPROCESSED_DATA_DIR: &str = "preprocessed_data";
@@ -20,26 +23,222 @@ const GRAPH_GPI_FILE: &str = "graph.gpi";
 const GRAPH_CONFIG_FILE: &str = "graph.conf";
 const PACK_DESCRIPTION_FILE: &str = "description.json";
 
+// Columns of the preprocessed data: size, min, Q1, median, Q3, max...
 fn add_plot(
     mut gnuplot_str: String,
     pack_name: &str,
-    scale_x: f64,
-    scale_y: f64,
+    config: &GraphConfig,
     filename: &str,
     description: &str,
+    line_color: usize,
 ) -> String {
     let data_path = format!(
         "\"{}/{}/{}/{}/{}.txt\"",
         PACKS_DIR, pack_name, TEMP_DIR, PREPROCESSED_DATA_DIR, filename
     );
-    let new_plot_str = format!(
-        "\t{} using ($1*({})):($4*({})) with linespoints title \"{}\", \\\n",
-        data_path, scale_x, scale_y, description
-    );
+    let x = format!("($1*({}))", config.x_scale);
+    let column = |index: usize| format!("(${}*({}))", index, config.y_scale);
+    let new_plot_str = match config.plot_style {
+        PlotStyle::Lines => format!(
+            "\t{} using {}:{} with linespoints lc {} title \"{}\", \\\n",
+            data_path,
+            x,
+            column(4),
+            line_color,
+            description
+        ),
+        PlotStyle::ErrorBars => format!(
+            "\t{} using {}:{}:{}:{} with yerrorlines lc {} title \"{}\", \\\n",
+            data_path,
+            x,
+            column(4),
+            column(3),
+            column(5),
+            line_color,
+            description
+        ),
+        // The median is drawn as a box of zero height
+        PlotStyle::Candlesticks => format!(
+            "\t{} using {}:{}:{}:{}:{} with candlesticks whiskerbars lc {} title \"{}\", \\\n\
+             \t{} using {}:{}:{}:{}:{} with candlesticks lc {} notitle, \\\n",
+            data_path,
+            x,
+            column(3),
+            column(2),
+            column(6),
+            column(5),
+            line_color,
+            description,
+            data_path,
+            x,
+            column(4),
+            column(4),
+            column(4),
+            column(4),
+            line_color
+        ),
+        PlotStyle::Bands => format!(
+            "\t{} using {}:{}:{} with filledcurves fs transparent solid 0.3 lc {} notitle, \\\n\
+             \t{} using {}:{} with linespoints lc {} title \"{}\", \\\n",
+            data_path,
+            x,
+            column(3),
+            column(5),
+            line_color,
+            data_path,
+            x,
+            column(4),
+            line_color,
+            description
+        ),
+    };
     gnuplot_str.push_str(&new_plot_str);
     gnuplot_str
 }
 
+fn graph_name<GenArgT>(
+    pack_description: &PackMeasuresDescription<GenArgT>,
+    config: &GraphConfig,
+) -> String
+where
+    GenArgT: std::fmt::Display,
+{
+    match config.metric {
+        Metric::Time => pack_description.filename.clone(),
+        metric => format!("{}_{}", pack_description.filename, metric.dir_name()),
+    }
+}
+
+fn value_label<GenArgT>(
+    pack_description: &PackMeasuresDescription<GenArgT>,
+    config: &GraphConfig,
+) -> String
+where
+    GenArgT: std::fmt::Display,
+{
+    // Time is shown in the unit chosen in graph.conf
+    match config.metric {
+        Metric::Time => match &pack_description.timer {
+            Some(timer) => {
+                format!(
+                    "{} ({}), {}",
+                    pack_description.y_label, timer, config.time_unit
+                )
+            }
+            None => format!("{}, {}", pack_description.y_label, config.time_unit),
+        },
+        metric => metric.to_string(),
+    }
+}
+
+// Separate surface or heatmap of every algorithm measured on a grid of sizes
+fn generate_grid_gpi<GenArgT>(
+    pack_description: &PackMeasuresDescription<GenArgT>,
+    config: &GraphConfig,
+) -> Result<()>
+where
+    GenArgT: std::fmt::Display,
+{
+    let gnuplot_config = std::include_str!("gnuplot_base_config.gpi");
+    let mut gnuplot_str = gnuplot_config.to_string();
+    gnuplot_str.push('\n');
+    gnuplot_str.push_str(format!("set term {}\n", config.output_type).as_str());
+    gnuplot_str.push_str("unset key\n");
+    gnuplot_str.push('\n');
+    gnuplot_str.push_str("#Labels\n");
+    gnuplot_str.push('\n');
+    gnuplot_str.push_str(format!("set xlabel \"{}\"\n", pack_description.x_label).as_str());
+    let second_size_label = pack_description
+        .second_size_label
+        .clone()
+        .unwrap_or_default();
+    gnuplot_str.push_str(format!("set ylabel \"{}\"\n", second_size_label).as_str());
+    let value_axis = match config.grid_style {
+        GridStyle::Surface => "z",
+        GridStyle::Heatmap => "cb",
+    };
+    gnuplot_str.push_str(
+        format!(
+            "set {}label \"{}\"\n",
+            value_axis,
+            value_label(pack_description, config)
+        )
+        .as_str(),
+    );
+    gnuplot_str.push('\n');
+    gnuplot_str.push_str("# Ranges\n");
+    // Both sizes are limited as x, the measured values as y
+    if config.x_start < config.x_end {
+        gnuplot_str
+            .push_str(format!("set xrange [{}:{}]\n", config.x_start, config.x_end).as_str());
+        gnuplot_str
+            .push_str(format!("set yrange [{}:{}]\n", config.x_start, config.x_end).as_str());
+    }
+    if config.y_start < config.y_end {
+        gnuplot_str.push_str(
+            format!(
+                "set {}range [{}:{}]\n",
+                value_axis, config.y_start, config.y_end
+            )
+            .as_str(),
+        );
+    }
+    // Both sizes are scaled as x, the measured values as y
+    if config.log_x {
+        gnuplot_str.push_str("set logscale xy 10\n");
+    }
+    if config.log_y {
+        gnuplot_str.push_str(format!("set logscale {} 10\n", value_axis).as_str());
+    }
+    gnuplot_str.push('\n');
+    match config.grid_style {
+        GridStyle::Surface => gnuplot_str.push_str("set hidden3d\nset pm3d\n"),
+        GridStyle::Heatmap => gnuplot_str.push_str("set view map\nset pm3d map\n"),
+    }
+    let graph_name = graph_name(pack_description, config);
+    for target_description in pack_description.target_descriptions.iter() {
+        let data_path = format!(
+            "\"{}/{}/{}/{}/{}.txt\"",
+            PACKS_DIR,
+            pack_description.filename,
+            TEMP_DIR,
+            PREPROCESSED_DATA_DIR,
+            target_description.filename
+        );
+        gnuplot_str.push('\n');
+        gnuplot_str.push_str(
+            format!(
+                "set output \"{}/{}/{}_{}_graph.{}\"\n",
+                PACKS_DIR,
+                pack_description.filename,
+                graph_name,
+                target_description.filename,
+                config.output_type
+            )
+            .as_str(),
+        );
+        gnuplot_str
+            .push_str(format!("set title \"{}\"\n", target_description.description).as_str());
+        // size a, size b, min, Q1, median...
+        gnuplot_str.push_str(
+            format!(
+                "splot {} using ($1*({})):($2*({})):($5*({})) with pm3d\n",
+                data_path, config.x_scale, config.x_scale, config.y_scale
+            )
+            .as_str(),
+        );
+    }
+    create_file_from_string(
+        format!(
+            "{}/{}/{}/{}",
+            PACKS_DIR, pack_description.filename, TEMP_DIR, GRAPH_GPI_FILE
+        )
+        .as_str(),
+        &gnuplot_str,
+    )?;
+    Ok(())
+}
+
 fn generate_pack_gpi<GenArgT>(
     pack_description: &PackMeasuresDescription<GenArgT>,
     config: &GraphConfig,
@@ -51,10 +250,11 @@ where
     let mut gnuplot_str = gnuplot_config.to_string();
     gnuplot_str.push('\n');
     gnuplot_str.push_str(format!("set term {}\n", config.output_type).as_str());
+    let graph_name = graph_name(pack_description, config);
     gnuplot_str.push_str(
         format!(
             "set output \"{}/{}/{}_graph.{}\"\n",
-            PACKS_DIR, pack_description.filename, pack_description.filename, config.output_type
+            PACKS_DIR, pack_description.filename, graph_name, config.output_type
         )
         .as_str(),
     );
@@ -64,10 +264,11 @@ where
     gnuplot_str.push('\n');
     gnuplot_str.push_str(format!("set xlabel \"{}\"\n", pack_description.x_label).as_str());
     gnuplot_str.push('\n');
-    gnuplot_str.push_str(format!("set ylabel \"{}\"\n", pack_description.y_label).as_str());
+    let y_label = value_label(pack_description, config);
+    gnuplot_str.push_str(format!("set ylabel \"{}\"\n", y_label).as_str());
     let mut ranges = String::new();
     ranges.push('\n');
-    ranges.push_str(format!("# Ranges\n").as_str());
+    ranges.push_str("# Ranges\n");
     if config.x_start < config.x_end {
         ranges.push_str(format!("set xrange [{}:{}]\n", config.x_start, config.x_end).as_str());
     }
@@ -88,14 +289,14 @@ where
         gnuplot_str.push_str("set logscale y 10\n\n"); // default logscale is log_10()
     }
     gnuplot_str.push_str("plot");
-    for target_description in pack_description.target_descriptions.iter() {
+    for (i, target_description) in pack_description.target_descriptions.iter().enumerate() {
         gnuplot_str = add_plot(
             gnuplot_str,
-            &pack_description.filename.as_str(),
-            config.x_scale,
-            config.y_scale,
+            pack_description.filename.as_str(),
+            config,
             &target_description.filename,
             &target_description.description,
+            i + 1,
         );
     }
     // for measure in pack_config.measures.iter() {
@@ -187,8 +388,26 @@ where
     let graph_config = GraphConfig::read(&graph_config_path)?;
 
     create_temp(pack_name)?;
-    let data_path =
-        PathBuf::from_str(format!("{}/{}/{}", PACKS_DIR, pack_name, DATA_DIR).as_str())?;
+    let data_path = match graph_config.metric {
+        Metric::Time => {
+            PathBuf::from_str(format!("{}/{}/{}", PACKS_DIR, pack_name, DATA_DIR).as_str())?
+        }
+        metric => PathBuf::from_str(
+            format!(
+                "{}/{}/{}/{}",
+                PACKS_DIR,
+                pack_name,
+                METRICS_DIR,
+                metric.dir_name()
+            )
+            .as_str(),
+        )?,
+    };
+    // Only time samples are scaled to the display unit
+    let (stored_unit, display_unit) = match graph_config.metric {
+        Metric::Time => (pack_description.time_unit, graph_config.time_unit),
+        _ => (TimeUnit::Nanoseconds, TimeUnit::Nanoseconds),
+    };
     let preprocessed_data_path = PathBuf::from_str(
         format!(
             "{}/{}/{}/{}",
@@ -199,17 +418,29 @@ where
     let csv_path = PathBuf::from_str(
         format!("{}/{}/{}/{}", PACKS_DIR, pack_name, TEMP_DIR, CSV_DIR).as_str(),
     )?;
-    preprocess::prepare_data(&data_path, &preprocessed_data_path, &csv_path)?;
-    let time_total_dir = PathBuf::from_str(
-        format!("{}/{}/", PACKS_DIR, pack_name).as_str(),
-    )?;
-    preprocess::create_time_total_csv(
-        &time_total_dir,
+    let is_grid = preprocess::prepare_data(
+        &data_path,
+        &preprocessed_data_path,
         &csv_path,
-        &pack_description.sizes,
-        &pack_description.threshold,
+        pack_description.outlier_policy,
+        stored_unit,
+        display_unit,
     )?;
-    generate_pack_gpi(&pack_description, &graph_config)?;
+    if graph_config.metric == Metric::Time && !is_grid {
+        let time_total_dir = PathBuf::from_str(format!("{}/{}/", PACKS_DIR, pack_name).as_str())?;
+        preprocess::create_time_total_csv(
+            &time_total_dir,
+            &csv_path,
+            &pack_description.sizes,
+            &pack_description.threshold,
+            graph_config.time_unit,
+        )?;
+    }
+    if is_grid {
+        generate_grid_gpi(&pack_description, &graph_config)?;
+    } else {
+        generate_pack_gpi(&pack_description, &graph_config)?;
+    }
     run_gnuplot(pack_name)?;
     if !graph_config.save_temp_files {
         clean_temp(pack_name)?;
@@ -1,6 +1,7 @@
+#[allow(clippy::module_inception)]
 pub mod graph;
 
 mod config;
-mod fileio;
+pub(crate) mod fileio;
 mod preprocess;
-mod quartiles;
+pub(crate) mod quartiles;
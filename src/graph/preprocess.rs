@@ -1,6 +1,9 @@
-use super::fileio::{get_filename, read_csv_file, recreate_dir_all};
+use super::fileio::{get_filename, read_csv_file, read_samples, recreate_dir_all};
 use super::quartiles::Quartiles;
 use crate::errors::{GraphError, GraphErrorRepr};
+use crate::sizes::GRID_SEPARATOR;
+use crate::statistics::{self, OutlierPolicy};
+use crate::units::TimeUnit;
 
 use anyhow::{Context, Result};
 use fs_err as fs;
@@ -12,15 +15,36 @@ use std::time::Duration;
 
 const TIME_RESULTS_CSV: &str = "total_time.csv";
 
+fn format_value(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{:.0}", value)
+    } else {
+        format!("{:.3}", value)
+    }
+}
+
+// Coordinates of a size file name, two for a grid size
+fn parse_size(basename: &str) -> Result<Vec<f64>> {
+    Ok(match basename.split_once(GRID_SEPARATOR) {
+        Some((a, b)) => vec![a.parse::<f64>()?, b.parse::<f64>()?],
+        None => vec![basename.parse::<f64>()?],
+    })
+}
+
+// Returns true if the sizes are grid sizes
 pub fn prepare_data(
     data_path: &Path,
     preprocessed_data_path: &Path,
     csv_path: &Path,
-) -> Result<()> {
+    outlier_policy: OutlierPolicy,
+    stored_unit: TimeUnit,
+    display_unit: TimeUnit,
+) -> Result<bool> {
     let pack_data_dir = fs::read_dir(data_path)?;
     recreate_dir_all(preprocessed_data_path)?;
     recreate_dir_all(csv_path)?;
 
+    let mut is_grid = false;
     for pack_data_dir_entry in pack_data_dir {
         let pack_data_dir_entry_path = pack_data_dir_entry?.path();
         if !pack_data_dir_entry_path.is_dir() {
@@ -49,50 +73,71 @@ pub fn prepare_data(
 
         let size_paths = fs::read_dir(&pack_data_dir_entry_path)?;
 
-        let mut int_lines: Vec<Vec<i32>> = Vec::new();
+        let mut lines: Vec<Vec<f64>> = Vec::new();
+        let mut coordinates_amount = 1;
 
         for size_path_result in size_paths {
             let size_path = size_path_result?.path();
             let filename = size_path.file_name().unwrap();
             let basename = filename.to_str().unwrap().split(".").next().unwrap();
 
-            let file = fs::File::open(&size_path)?;
-
-            let reader = BufReader::new(file);
-            let mut res_vec = reader
-                .lines()
-                .scan((), |_, x| x.ok())
-                .map(|x| x.parse::<i32>().unwrap())
-                .collect::<Vec<i32>>();
-            res_vec.sort();
-            // println!("file: {}", size_path.as_os_str().to_str().unwrap());
-            // println!("sorted: {:#?}", res_vec);
-            let quart = Quartiles::new(&res_vec);
+            // Every line holds one measurement: either a single batch average
+            // or the whitespace-separated samples of every iteration
+            let float_values = read_samples(&size_path)?
+                .into_iter()
+                .map(|x| stored_unit.convert(x, display_unit))
+                .collect::<Vec<f64>>();
+            let outlier_counts = statistics::classify_outliers(&float_values);
+            let float_values = statistics::apply_outlier_policy(&float_values, outlier_policy);
+            let quart = Quartiles::new(&float_values);
             let quart_values = quart.values();
-
-            int_lines.push(vec![
-                basename.parse::<i32>().unwrap(),
-                quart_values[0] as i32,
-                quart_values[1] as i32,
-                quart_values[2] as i32,
-                quart_values[3] as i32,
-                quart_values[4] as i32,
+            let mean_interval = statistics::mean_confidence_interval(&float_values);
+            let median_interval = statistics::median_confidence_interval(&float_values);
+
+            // size, min, Q1, median, Q3, max, mean, mean CI, median CI, mild and severe outliers.
+            // A grid size takes two columns
+            let mut line = parse_size(basename)?;
+            coordinates_amount = line.len();
+            line.extend([
+                quart_values[0],
+                quart_values[1],
+                quart_values[2],
+                quart_values[3],
+                quart_values[4],
+                mean_interval.estimate,
+                mean_interval.lower,
+                mean_interval.upper,
+                median_interval.lower,
+                median_interval.upper,
+                outlier_counts.mild as f64,
+                outlier_counts.severe as f64,
             ]);
+            lines.push(line);
         }
 
-        int_lines.sort_by(|a, b| a[0].partial_cmp(&b[0]).unwrap());
+        lines.sort_by(|a, b| match a[0].total_cmp(&b[0]) {
+            std::cmp::Ordering::Equal => a[1].total_cmp(&b[1]),
+            other => other,
+        });
+        is_grid |= coordinates_amount == 2;
 
         {
-            let full_stats = int_lines
-                .iter()
-                .map(|x: &Vec<i32>| {
-                    x.iter()
-                        .map(|y| y.to_string())
+            let mut full_stats = String::new();
+            for (i, x) in lines.iter().enumerate() {
+                // splot takes the rows of a grid separated by blank lines
+                if i > 0 {
+                    if coordinates_amount == 2 && lines[i - 1][0] != x[0] {
+                        full_stats.push('\n');
+                    }
+                    full_stats.push('\n');
+                }
+                full_stats.push_str(
+                    &x.iter()
+                        .map(|y| format_value(*y))
                         .collect::<Vec<String>>()
-                        .join(" ")
-                })
-                .collect::<Vec<String>>()
-                .join("\n");
+                        .join(" "),
+                );
+            }
 
             let algorithm_full_stat_path = preprocessed_data_path
                 .join(PathBuf::from_str(format!("{}.txt", algo_name).as_str())?);
@@ -106,9 +151,15 @@ pub fn prepare_data(
             let algorithm_simple_stat_path =
                 csv_path.join(PathBuf::from_str(format!("{}.csv", algo_name).as_str())?);
 
-            let simple_stats = int_lines
+            let simple_stats = lines
                 .iter()
-                .map(|x: &Vec<i32>| format!("{} {}", x[0].to_string(), x[1].to_string()))
+                .map(|x: &Vec<f64>| {
+                    x[..=coordinates_amount]
+                        .iter()
+                        .map(|y| format_value(*y))
+                        .collect::<Vec<String>>()
+                        .join(" ")
+                })
                 .collect::<Vec<String>>()
                 .join("\n");
 
@@ -118,7 +169,7 @@ pub fn prepare_data(
         }
     }
 
-    Ok(())
+    Ok(is_grid)
 }
 
 pub fn create_time_total_csv<GenArgT>(
@@ -126,6 +177,7 @@ pub fn create_time_total_csv<GenArgT>(
     csv_path: &Path,
     sizes: &[GenArgT],
     threshold: &Duration,
+    display_unit: TimeUnit,
 ) -> Result<()>
 where
     GenArgT: std::fmt::Display,
@@ -133,6 +185,11 @@ where
     use std::cmp::Ordering;
     fn separate_digits_by_groups(row_value_str: &str) -> String {
         static DIGITS_IN_GROUP: usize = 3;
+        // Only the integer part is grouped
+        let (row_value_str, fraction_str) = match row_value_str.split_once('.') {
+            Some((integer, fraction)) => (integer, format!(".{}", fraction)),
+            None => (row_value_str, String::new()),
+        };
         let mut out_str = String::new();
         let chars = row_value_str.chars().collect::<Vec<char>>();
         let length = chars.len();
@@ -140,17 +197,18 @@ where
         let offset = (DIGITS_IN_GROUP - (length) % DIGITS_IN_GROUP) % DIGITS_IN_GROUP;
 
         out_str.push(chars[0]);
-        for i in 1..length {
-            if i < length - 1 && (i + offset) % DIGITS_IN_GROUP == 0 {
+        for (i, char) in chars.iter().enumerate().skip(1) {
+            if i < length - 1 && (i + offset).is_multiple_of(DIGITS_IN_GROUP) {
                 out_str.push(' ');
             }
-            out_str.push(chars[i]);
+            out_str.push(*char);
         }
+        out_str.push_str(&fraction_str);
 
         out_str
     }
 
-    let mut file_names_with_peak_time: Vec<(String, (usize, usize))> = Vec::new();
+    let mut file_names_with_peak_time: Vec<(String, (f64, f64))> = Vec::new();
     let pack_csv_dir = fs::read_dir(csv_path)?;
     for pack_data_dir_entry in pack_csv_dir {
         let pack_csv_dir_entry_path = pack_data_dir_entry?.path();
@@ -168,15 +226,15 @@ where
             .lines()
             .scan((), |_, x| x.ok())
             .map(|x| {let mut split = x.split(' ');
-                              (split.next().unwrap().parse::<i32>().unwrap(),
-                               split.next().unwrap().parse::<i32>().unwrap())})
+                              (split.next().unwrap().parse::<f64>().unwrap(),
+                               split.next().unwrap().parse::<f64>().unwrap())})
             .max_by(|a,b|
-                match a.0.cmp(&b.0) {
-                    Ordering::Equal => a.1.cmp(&b.1),
+                match a.0.total_cmp(&b.0) {
+                    Ordering::Equal => a.1.total_cmp(&b.1),
                     other => other,
                 });
 
-        if !peak_time_measure.is_some_and(|x| x.0 >= 0 && x.1 >= 0) {
+        if !peak_time_measure.is_some_and(|x| x.0 >= 0.0 && x.1 >= 0.0) {
             eprintln!(
                 "{}: Измеренное время и размер данных не могут быть меньше нуля",
                 pack_csv_dir_entry_path.display(),
@@ -187,7 +245,7 @@ where
         }
 
         let peak_time_measure = peak_time_measure.unwrap();
-        let peak_time_measure = (peak_time_measure.0 as usize, peak_time_measure.1 as usize);
+
         file_names_with_peak_time.push((algorithm_name.to_owned(), peak_time_measure));
 
         // eprintln!(
@@ -196,8 +254,8 @@ where
         // );
     }
     file_names_with_peak_time.sort_by(|a, b|
-        match b.1.0.cmp(&a.1.0) {
-            Ordering::Equal => a.1.1.cmp(&b.1.1),
+        match b.1.0.total_cmp(&a.1.0) {
+            Ordering::Equal => a.1.1.total_cmp(&b.1.1),
             other => other,
         });
 
@@ -234,7 +292,7 @@ where
         }
         for i in rows.len()..merged_rows.len()-1 {
             merged_rows[i+1].push_str(",>");
-            let formatted_value = separate_digits_by_groups(format!("{}", threshold.as_nanos()).as_str());
+            let formatted_value = separate_digits_by_groups(format_value(display_unit.from_duration(*threshold)).as_str());
             merged_rows[i+1].push_str(formatted_value.as_str());
         }
     }
@@ -29,19 +29,23 @@ impl Quartiles {
 
     pub fn values(&self) -> [f64; 5] {
         [
-            self.bottom_boundary as f64,
-            self.lower as f64,
-            self.median as f64,
-            self.upper as f64,
-            self.top_boundary as f64,
+            self.bottom_boundary,
+            self.lower,
+            self.median,
+            self.upper,
+            self.top_boundary,
         ]
     }
 
-    fn quantile<T: Into<f64> + Copy>(slice: &[T], percent: f64) -> f64 {
+    pub fn median(&self) -> f64 {
+        self.median
+    }
+
+    pub(crate) fn quantile<T: Into<f64> + Copy>(slice: &[T], percent: f64) -> f64 {
         assert!(percent >= 0.0);
         assert!(percent <= 100.0);
 
-        if slice.len() == 0 {
+        if slice.is_empty() {
             return f64::NAN;
         }
         if slice.len() == 1 {
@@ -1,6 +1,16 @@
+pub mod allocator;
+pub mod comparison;
+pub mod complexity;
 pub mod errors;
 pub mod graph;
 pub mod measures;
+pub mod metrics;
+pub mod sizes;
+pub mod statistics;
+pub mod units;
 
+mod affinity;
+mod checkpoint;
 mod description;
+mod environment;
 mod nix_function_threshold;
@@ -1,14 +1,30 @@
+use crate::affinity::AffinityGuard;
+use crate::checkpoint::{self, CheckpointEntry, CheckpointPack, CheckpointWriter};
+use crate::comparison::{self, PackComparison, PackSamples};
+use crate::complexity::{self, ComplexityFit};
 use crate::description;
+use crate::environment::{self, Environment};
+use crate::graph::fileio::rescale_samples;
+use crate::graph::quartiles::Quartiles;
+use crate::metrics::{self, Metric, ResourceUsage};
+pub use crate::nix_function_threshold::ProbeOutcome;
+use crate::sizes::{self, SizesSpec};
+use crate::statistics::{self, ConfidenceInterval, OutlierCounts, OutlierPolicy};
+use crate::units::TimeUnit;
 
 use cpu_time::{ProcessTime, ThreadTime};
 use fs_err as fs;
 use indexmap::IndexMap;
+use num::ToPrimitive;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
 
 use std::cell::RefCell;
-// use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
-use std::ops::DerefMut;
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::time::Duration;
@@ -16,6 +32,19 @@ use std::time::SystemTime;
 
 const PACKS_DIR: &str = "packs";
 const DATA_DIR: &str = "data";
+const METRICS_DIR: &str = "metrics";
+const BASELINES_DIR: &str = "baselines";
+const DESCRIPTION_FILE: &str = "description.json";
+const CHECKPOINT_FILE: &str = "checkpoint.jsonl";
+const TIMER_RESOLUTION_SAMPLES: usize = 10;
+// Wall time after which a timer that did not advance is given up on
+const TIMER_RESOLUTION_TIMEOUT: Duration = Duration::from_millis(100);
+// Calibrated batch lasts at least this many timer ticks
+const MIN_BATCH_TIMER_TICKS: u32 = 1000;
+const MAX_CALIBRATED_ITERATIONS: u64 = 1_000_000;
+// Inputs of a measurement are generated in chunks of this many, so that fast
+// calls on large inputs do not keep all their inputs in memory at once
+const MAX_PREGENERATED_INPUTS: u64 = 1024;
 
 pub enum TimerType {
     ProcessTimer,
@@ -23,15 +52,185 @@ pub enum TimerType {
     SystemTimer,
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum WarmUp {
+    #[default]
+    Disabled,
+    Iterations(u64),
+    Time(Duration),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum SamplingMode {
+    // One sample per measurement: average time of a batch of iterations
+    #[default]
+    Batch,
+    // Every iteration is timed separately and kept as its own sample
+    PerIteration,
+}
+
+// Generator shared by all targets of a pack
+struct PackGenerator<'c, GenArgT, AlgArgT> {
+    generator: RefCell<Generator<'c, GenArgT, AlgArgT>>,
+    clone_input: fn(&AlgArgT) -> AlgArgT,
+    // Inputs of every iteration of the size index and measurement the
+    // targets are running on, dropped when the next ones start
+    input_key: Option<(usize, u64)>,
+    inputs: Vec<AlgArgT>,
+}
+
+// Compares input after the call and result of two algorithms
+pub type ResultComparator<'a, AlgArgT, AlgResT> =
+    Box<dyn Fn(&(AlgArgT, AlgResT), &(AlgArgT, AlgResT)) -> bool + 'a>;
+
+struct Verification<'a, AlgArgT, AlgResT> {
+    comparator: ResultComparator<'a, AlgArgT, AlgResT>,
+    clone_input: fn(&AlgArgT) -> AlgArgT,
+}
+
+#[derive(Debug, Clone)]
+pub struct ResultMismatch {
+    pub size: String,
+    pub reference: String,
+    pub target: String,
+}
+
+impl fmt::Display for ResultMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Размер {}: результаты {} и {} не совпадают",
+            self.size, self.reference, self.target
+        )
+    }
+}
+
+// Settings of a pack applied to every measurement
+#[derive(Debug, Clone, Copy)]
+struct MeasureSettings {
+    warm_up: WarmUp,
+    sampling_mode: SamplingMode,
+    process_isolation: bool,
+    // Time limit of a call, set if the threshold is used
+    threshold: Option<Duration>,
+    memory_threshold: Option<u64>,
+}
+
+impl MeasureSettings {
+    // Time limit of an isolated measurement: every call gets the threshold
+    // and the time warm-up may run over by one call
+    fn isolation_timeout(&self, iterations_amount: u64) -> Option<Duration> {
+        let threshold = self.threshold?;
+        let (warm_up_calls, warm_up_time) = match self.warm_up {
+            WarmUp::Disabled => (0, Duration::ZERO),
+            WarmUp::Iterations(iterations_amount) => (iterations_amount, Duration::ZERO),
+            WarmUp::Time(duration) => (1, duration),
+        };
+        let calls =
+            u32::try_from(iterations_amount.saturating_add(warm_up_calls)).unwrap_or(u32::MAX);
+        threshold
+            .checked_mul(calls)
+            .and_then(|timeout| timeout.checked_add(warm_up_time))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ExecutionOrder {
+    // Every algorithm is measured on all sizes and repetitions in turn
+    #[default]
+    Sequential,
+    // Algorithms take turns on every size of every repetition
+    Interleaved,
+    // Interleaved measurements of every repetition are shuffled with the seed
+    Randomized(u64),
+}
+
+impl ExecutionOrder {
+    // Randomized order with a seed taken from the system time
+    pub fn randomized() -> Self {
+        let seed = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|x| x.as_nanos() as u64)
+            .unwrap_or_default();
+        Self::Randomized(seed)
+    }
+}
+
+// How the maximal size under the threshold is searched for. Galloping and
+// binary searches assume the run time grows with the size
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ThresholdSearch {
+    // Every size is probed in increasing order
+    #[default]
+    Linear,
+    // Sizes 1, 2, 4, 8... are probed until one fails, then the last step is bisected
+    Galloping,
+    // All the sizes are bisected
+    Binary,
+}
+
+// Samples of one measurement of one size
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct SizeMeasurement {
+    pub times: Vec<Duration>,
+    pub metrics: Vec<(Metric, f64)>,
+}
+
 pub trait Timer {
     fn now() -> Self;
     fn elapsed(&self) -> Duration;
+
+    // Recorded in the pack description and shown in axis labels
+    fn name() -> String
+    where
+        Self: Sized,
+    {
+        std::any::type_name::<Self>().to_string()
+    }
+
+    // Smallest non-zero interval the timer is able to observe. Zero if the
+    // timer did not advance in time, e.g. a simulated clock
+    fn resolution() -> Duration
+    where
+        Self: Sized,
+    {
+        let deadline = std::time::Instant::now() + TIMER_RESOLUTION_TIMEOUT;
+        let mut resolution = Duration::MAX;
+        for _ in 0..TIMER_RESOLUTION_SAMPLES {
+            let stopwatch = Self::now();
+            loop {
+                let elapsed = stopwatch.elapsed();
+                if !elapsed.is_zero() {
+                    resolution = resolution.min(elapsed);
+                    break;
+                }
+                if std::time::Instant::now() >= deadline {
+                    break;
+                }
+            }
+            if std::time::Instant::now() >= deadline {
+                break;
+            }
+        }
+        if resolution == Duration::MAX {
+            eprintln!(
+                "Таймер \"{}\" не изменился за {:?}, разрешение не учитывается",
+                Self::name(),
+                TIMER_RESOLUTION_TIMEOUT
+            );
+            return Duration::ZERO;
+        }
+        resolution
+    }
 }
 
 impl Timer for ProcessTime {
     fn now() -> Self {
         Self::now()
     }
+    fn name() -> String {
+        String::from("Процессорное время процесса")
+    }
     fn elapsed(&self) -> Duration {
         self.elapsed()
     }
@@ -41,6 +240,9 @@ impl Timer for ThreadTime {
     fn now() -> Self {
         Self::now()
     }
+    fn name() -> String {
+        String::from("Процессорное время потока")
+    }
     fn elapsed(&self) -> Duration {
         self.elapsed()
     }
@@ -50,11 +252,33 @@ impl Timer for SystemTime {
     fn now() -> Self {
         Self::now()
     }
+    fn name() -> String {
+        String::from("Системное время")
+    }
     fn elapsed(&self) -> Duration {
         self.elapsed().unwrap()
     }
 }
 
+pub type Generator<'b, GenArgT, AlgArgT> = Box<dyn FnMut(&GenArgT) -> AlgArgT + 'b>;
+
+// Measuring functions monomorphized for the timer chosen in a pack
+type MeasureFn<'a, 'c, GenArgT, AlgArgT, AlgResT> =
+    fn(
+        &MeasurableAlgorithm<'a, 'c, GenArgT, AlgArgT, AlgResT>,
+        &mut dyn FnMut(&GenArgT) -> AlgArgT,
+        Option<&mut dyn FnMut(&GenArgT) -> AlgArgT>,
+        &[GenArgT],
+        &[u64],
+        MeasureSettings,
+    ) -> Vec<Result<SizeMeasurement, ProbeOutcome>>;
+type CalibrateFn<'a, 'c, GenArgT, AlgArgT, AlgResT> = fn(
+    &MeasurableAlgorithm<'a, 'c, GenArgT, AlgArgT, AlgResT>,
+    &mut dyn FnMut(&GenArgT) -> AlgArgT,
+    &[GenArgT],
+    Duration,
+) -> Vec<u64>;
+
 pub enum Algorithm<'a, AlgArgT, AlgResT> {
     NonMutatingAlgorithm(Box<dyn Fn(&AlgArgT) -> AlgResT + 'a>),
     MutatingAlgorithm(Box<dyn Fn(&mut AlgArgT) -> AlgResT + 'a>),
@@ -67,7 +291,7 @@ where
     pub filename: String,
     pub description: String,
     pub algorithm: Algorithm<'a, AlgArgT, AlgResT>,
-    pub generator: RefCell<Box<dyn FnMut(&GenArgT) -> AlgArgT + 'b>>,
+    pub generator: RefCell<Generator<'b, GenArgT, AlgArgT>>,
     current_data: Option<AlgArgT>,
     gen_arg: PhantomData<GenArgT>,
     alg_arg: PhantomData<AlgArgT>,
@@ -81,7 +305,7 @@ where
     pub fn new(
         description: &str,
         algorithm: Box<dyn Fn(&AlgArgT) -> AlgResT + 'a>,
-        generator: Box<dyn FnMut(&GenArgT) -> AlgArgT + 'b>,
+        generator: Generator<'b, GenArgT, AlgArgT>,
     ) -> Self {
         Self {
             description: description.to_string(),
@@ -98,7 +322,7 @@ where
     pub fn new_mut(
         description: &str,
         algorithm: Box<dyn Fn(&mut AlgArgT) -> AlgResT + 'a>,
-        generator: Box<dyn FnMut(&GenArgT) -> AlgArgT + 'b>,
+        generator: Generator<'b, GenArgT, AlgArgT>,
     ) -> Self {
         Self {
             description: description.to_string(),
@@ -121,86 +345,427 @@ where
         self.current_data = Some(data);
     }
 
-    fn measure<TimerT>(&self, sizes: &[GenArgT], iterations_amount: u64) -> Vec<Duration>
-    where
-        TimerT: Timer,
-    {
-        let mut elapsed_time_for_sizes: Vec<Duration> = Vec::new();
+    fn run(&self, mut data: AlgArgT) {
+        match &self.algorithm {
+            Algorithm::NonMutatingAlgorithm(algorithm) => _ = algorithm(&data),
+            Algorithm::MutatingAlgorithm(algorithm) => _ = algorithm(&mut data),
+        }
+    }
 
-        let mut generator = self.generator.borrow_mut();
+    // Input after the call together with the result
+    fn run_with_result(&self, mut data: AlgArgT) -> (AlgArgT, AlgResT) {
+        let result = match &self.algorithm {
+            Algorithm::NonMutatingAlgorithm(algorithm) => algorithm(&data),
+            Algorithm::MutatingAlgorithm(algorithm) => algorithm(&mut data),
+        };
+        (data, result)
+    }
 
-        for size in sizes.iter() {
-            let mut current_elapsed_time = Duration::new(0, 0);
+    fn warm_up(
+        &self,
+        generator: &mut dyn FnMut(&GenArgT) -> AlgArgT,
+        size: &GenArgT,
+        warm_up: WarmUp,
+    ) {
+        match warm_up {
+            WarmUp::Disabled => {}
+            WarmUp::Iterations(iterations_amount) => {
+                for _ in 0..iterations_amount {
+                    let data = generator(size);
+                    self.run(data);
+                }
+            }
+            WarmUp::Time(duration) => {
+                // Only the algorithm runs count towards the warm-up time
+                let mut elapsed = Duration::new(0, 0);
+                while elapsed < duration {
+                    let data = generator(size);
+                    let time = std::time::Instant::now();
+                    self.run(data);
+                    elapsed += time.elapsed();
+                }
+            }
+        }
+    }
 
+    // Runs `run` on chunks of freshly generated inputs, the metrics of every
+    // chunk are taken without the generation and merged
+    fn measure_chunks(
+        &self,
+        generator: &mut dyn FnMut(&GenArgT) -> AlgArgT,
+        size: &GenArgT,
+        iterations_amount: u64,
+        run: &mut dyn FnMut(Vec<AlgArgT>),
+    ) -> Vec<(Metric, f64)> {
+        let mut chunks_metrics = vec![];
+        let mut iterations_left = iterations_amount;
+        while iterations_left > 0 {
+            let chunk = iterations_left.min(MAX_PREGENERATED_INPUTS);
             let mut data = vec![];
-            for _ in 0..iterations_amount {
-                data.push((generator.deref_mut())(size));
+            for _ in 0..chunk {
+                data.push(generator(size));
             }
 
-            let stopwatch = TimerT::now();
+            let resource_usage = ResourceUsage::start();
+            run(data);
+            chunks_metrics.push((
+                ResourceUsage::now().metrics_since(&resource_usage, chunk),
+                chunk,
+            ));
+            iterations_left -= chunk;
+        }
+        metrics::merge_metrics(&chunks_metrics)
+    }
+
+    // Total time of `iterations_amount` calls on freshly generated inputs
+    fn measure_batch<TimerT>(
+        &self,
+        generator: &mut dyn FnMut(&GenArgT) -> AlgArgT,
+        size: &GenArgT,
+        iterations_amount: u64,
+    ) -> SizeMeasurement
+    where
+        TimerT: Timer,
+    {
+        let mut elapsed = Duration::ZERO;
+        let metrics =
+            self.measure_chunks(generator, size, iterations_amount, &mut |mut data: Vec<
+                AlgArgT,
+            >| {
+                let stopwatch = TimerT::now();
+
+                match &self.algorithm {
+                    Algorithm::NonMutatingAlgorithm(algorithm) => {
+                        while let Some(curr_data) = data.pop() {
+                            _ = algorithm(&curr_data);
+                        }
+                    }
+                    Algorithm::MutatingAlgorithm(algorithm) => {
+                        while let Some(mut curr_data) = data.pop() {
+                            _ = algorithm(&mut curr_data);
+                        }
+                    }
+                }
 
-            match &self.algorithm {
+                elapsed += stopwatch.elapsed();
+            });
+        SizeMeasurement {
+            times: vec![elapsed],
+            metrics,
+        }
+    }
+
+    fn measure_iterations<TimerT>(
+        &self,
+        generator: &mut dyn FnMut(&GenArgT) -> AlgArgT,
+        size: &GenArgT,
+        iterations_amount: u64,
+    ) -> SizeMeasurement
+    where
+        TimerT: Timer,
+    {
+        let mut samples = Vec::with_capacity(iterations_amount as usize);
+        let metrics =
+            self.measure_chunks(generator, size, iterations_amount, &mut |mut data: Vec<
+                AlgArgT,
+            >| match &self
+                .algorithm
+            {
                 Algorithm::NonMutatingAlgorithm(algorithm) => {
-                    for _ in 0..iterations_amount as usize {
-                        let curr_data = data.pop().unwrap();
-                        _ = algorithm(&curr_data);
+                    for curr_data in data.iter() {
+                        let stopwatch = TimerT::now();
+                        _ = algorithm(curr_data);
+                        samples.push(stopwatch.elapsed());
                     }
                 }
                 Algorithm::MutatingAlgorithm(algorithm) => {
-                    for _ in 0..iterations_amount as usize {
-                        let mut curr_data = data.pop().unwrap();
-                        _ = algorithm(&mut curr_data);
+                    for curr_data in data.iter_mut() {
+                        let stopwatch = TimerT::now();
+                        _ = algorithm(curr_data);
+                        samples.push(stopwatch.elapsed());
                     }
                 }
+            });
+
+        SizeMeasurement {
+            times: samples,
+            metrics,
+        }
+    }
+
+    // Warm-up takes its inputs from `warm_up_generator` if it is set
+    fn measure_size<TimerT>(
+        &self,
+        generator: &mut dyn FnMut(&GenArgT) -> AlgArgT,
+        warm_up_generator: Option<&mut dyn FnMut(&GenArgT) -> AlgArgT>,
+        size: &GenArgT,
+        iterations_amount: u64,
+        settings: MeasureSettings,
+    ) -> SizeMeasurement
+    where
+        TimerT: Timer,
+    {
+        match warm_up_generator {
+            Some(warm_up_generator) => self.warm_up(warm_up_generator, size, settings.warm_up),
+            None => self.warm_up(generator, size, settings.warm_up),
+        }
+
+        match settings.sampling_mode {
+            SamplingMode::Batch => {
+                let mut measurement =
+                    self.measure_batch::<TimerT>(generator, size, iterations_amount);
+                measurement.times[0] = measurement.times[0]
+                    .checked_div(iterations_amount as u32)
+                    .expect("Ошибка: количество повторов измерения равно нулю");
+                measurement
+            }
+            SamplingMode::PerIteration => {
+                self.measure_iterations::<TimerT>(generator, size, iterations_amount)
             }
+        }
+    }
 
-            current_elapsed_time += stopwatch.elapsed();
+    fn measure<TimerT>(
+        &self,
+        generator: &mut dyn FnMut(&GenArgT) -> AlgArgT,
+        mut warm_up_generator: Option<&mut dyn FnMut(&GenArgT) -> AlgArgT>,
+        sizes: &[GenArgT],
+        iterations_amounts: &[u64],
+        settings: MeasureSettings,
+    ) -> Vec<Result<SizeMeasurement, ProbeOutcome>>
+    where
+        TimerT: Timer,
+    {
+        use crate::nix_function_threshold;
+        let mut elapsed_time_for_sizes = Vec::new();
 
-            current_elapsed_time = current_elapsed_time
-                .checked_div(iterations_amount as u32)
-                .expect("Ошибка: количество повторов измерения равно нулю");
-            elapsed_time_for_sizes.push(current_elapsed_time);
+        for (size, iterations_amount) in sizes.iter().zip(iterations_amounts) {
+            let samples = if settings.process_isolation {
+                // The child reports how many inputs it has generated with
+                // every generator and the parent repeats the calls, so the
+                // generator state carries over to the next sizes without
+                // keeping the inputs
+                let result = unsafe {
+                    nix_function_threshold::call_in_child(
+                        || {
+                            let mut generator_calls = 0u64;
+                            let mut warm_up_generator_calls = 0u64;
+                            let mut counted_generator = |size: &GenArgT| {
+                                generator_calls += 1;
+                                generator(size)
+                            };
+                            let measurement = match warm_up_generator.as_mut() {
+                                Some(warm_up_generator) => self.measure_size::<TimerT>(
+                                    &mut counted_generator,
+                                    Some(&mut |size: &GenArgT| {
+                                        warm_up_generator_calls += 1;
+                                        warm_up_generator(size)
+                                    }),
+                                    size,
+                                    *iterations_amount,
+                                    settings,
+                                ),
+                                None => self.measure_size::<TimerT>(
+                                    &mut counted_generator,
+                                    None,
+                                    size,
+                                    *iterations_amount,
+                                    settings,
+                                ),
+                            };
+                            (measurement, generator_calls, warm_up_generator_calls)
+                        },
+                        settings.isolation_timeout(*iterations_amount),
+                        settings.memory_threshold,
+                    )
+                };
+                result.map(|(measurement, generator_calls, warm_up_generator_calls)| {
+                    if let Some(warm_up_generator) = warm_up_generator.as_mut() {
+                        for _ in 0..warm_up_generator_calls {
+                            warm_up_generator(size);
+                        }
+                    }
+                    for _ in 0..generator_calls {
+                        generator(size);
+                    }
+                    measurement
+                })
+            } else {
+                Ok(self.measure_size::<TimerT>(
+                    generator,
+                    warm_up_generator
+                        .as_mut()
+                        .map(|x| &mut **x as &mut dyn FnMut(&GenArgT) -> AlgArgT),
+                    size,
+                    *iterations_amount,
+                    settings,
+                ))
+            };
+            elapsed_time_for_sizes.push(samples);
         }
 
         elapsed_time_for_sizes
     }
+
+    // Smallest iterations amount for every size so that a batch lasts at
+    // least `min_batch_time` and many ticks of the timer
+    fn calibrate_iterations<TimerT>(
+        &self,
+        generator: &mut dyn FnMut(&GenArgT) -> AlgArgT,
+        sizes: &[GenArgT],
+        min_batch_time: Duration,
+    ) -> Vec<u64>
+    where
+        TimerT: Timer,
+    {
+        let resolution = TimerT::resolution();
+        let target_time = min_batch_time.max(resolution.saturating_mul(MIN_BATCH_TIMER_TICKS));
+
+        let mut iterations_amounts = vec![];
+        for size in sizes.iter() {
+            let mut iterations_amount = 1;
+            loop {
+                let elapsed = self
+                    .measure_batch::<TimerT>(generator, size, iterations_amount)
+                    .times[0];
+                if elapsed >= target_time || iterations_amount >= MAX_CALIBRATED_ITERATIONS {
+                    break;
+                }
+                let next_iterations_amount = if elapsed.is_zero() {
+                    iterations_amount * 10
+                } else {
+                    let ratio = target_time.as_secs_f64() / elapsed.as_secs_f64();
+                    // 10% margin, but grow at most tenfold per step
+                    ((iterations_amount as f64 * ratio * 1.1).ceil() as u64)
+                        .min(iterations_amount * 10)
+                };
+                iterations_amount = next_iterations_amount
+                    .max(iterations_amount + 1)
+                    .min(MAX_CALIBRATED_ITERATIONS);
+            }
+            iterations_amounts.push(iterations_amount);
+        }
+        iterations_amounts
+    }
 }
 
 impl<'a, 'b, GenArgT, AlgArgT, AlgResT> MeasurableAlgorithm<'a, 'b, GenArgT, AlgArgT, AlgResT>
 where
     GenArgT: std::fmt::Display,
 {
-    fn calculate_max_data_size(&self, sizes: &[GenArgT], threshold: Duration) -> usize {
+    // Outcomes of the probe for every size up to the first failed one, None
+    // for the sizes skipped by the search
+    fn calculate_max_data_size(
+        &self,
+        generator: &mut dyn FnMut(&GenArgT) -> AlgArgT,
+        sizes: &[GenArgT],
+        threshold: Option<Duration>,
+        memory_threshold: Option<u64>,
+        search: ThresholdSearch,
+    ) -> Vec<Option<ProbeOutcome>> {
         use crate::nix_function_threshold;
         use std::io::{stdout, Write};
-        let mut max_size_index: usize = 0;
-        let mut generator = self.generator.borrow_mut();
-        let mut lock = stdout().lock();
-        unsafe {
-            println!("Алгоритм: {}", self.description);
-            for size in sizes {
-                let data = (generator.deref_mut())(size);
-                let res = nix_function_threshold::call_long_running_function(
+        println!("Алгоритм: {}", self.description);
+        let mut outcomes = vec![None; sizes.len()];
+        let mut probe = |size_index: usize| {
+            let size = &sizes[size_index];
+            let data = generator(size);
+            let outcome = unsafe {
+                nix_function_threshold::call_long_running_function(
                     &self.algorithm,
                     data,
                     threshold,
-                );
-                if res == false {
-                    break;
-                }
+                    memory_threshold,
+                )
+            };
+            let mut lock = stdout().lock();
+            if outcome.is_completed() {
                 write!(
                     lock,
                     "Максимальный линейный размер входных данных: {}\t\r",
                     size
                 )
                 .unwrap();
-                _ = std::io::stdout().flush();
-                max_size_index += 1;
+                _ = lock.flush();
+            } else if outcome != ProbeOutcome::TimedOut {
+                writeln!(lock, "\nРазмер {}: {}", size, outcome).unwrap();
+            }
+            outcomes[size_index] = Some(outcome);
+            outcome
+        };
+        let (max_size_number, failure) = search_max_size_number(sizes.len(), search, &mut probe);
+        println!();
+        outcomes.truncate(max_size_number + usize::from(failure.is_some()));
+        outcomes
+    }
+}
+
+// Amount of the first sizes completed under the threshold and the outcome of
+// the size after them
+fn search_max_size_number(
+    sizes_amount: usize,
+    search: ThresholdSearch,
+    probe: &mut dyn FnMut(usize) -> ProbeOutcome,
+) -> (usize, Option<ProbeOutcome>) {
+    // Sizes before `low` are completed, the size at `high` has failed
+    let mut low = 0;
+    let mut high = sizes_amount;
+    let mut failure = None;
+    match search {
+        ThresholdSearch::Linear => return linear_search(sizes_amount, probe),
+        ThresholdSearch::Galloping => {
+            let mut size_index = 0;
+            while size_index < sizes_amount {
+                let outcome = probe(size_index);
+                if !outcome.is_completed() {
+                    high = size_index;
+                    failure = Some(outcome);
+                    break;
+                }
+                low = size_index + 1;
+                size_index = 2 * size_index + 1;
             }
-            println!();
         }
-        max_size_index
+        ThresholdSearch::Binary => {}
+    }
+    if failure.is_some_and(|outcome| !is_monotonic(outcome)) {
+        return linear_search(sizes_amount, probe);
+    }
+    while low < high {
+        let middle = low + (high - low) / 2;
+        let outcome = probe(middle);
+        if outcome.is_completed() {
+            low = middle + 1;
+        } else if is_monotonic(outcome) {
+            high = middle;
+            failure = Some(outcome);
+        } else {
+            return linear_search(sizes_amount, probe);
+        }
+    }
+    (low, failure)
+}
+
+fn linear_search(
+    sizes_amount: usize,
+    probe: &mut dyn FnMut(usize) -> ProbeOutcome,
+) -> (usize, Option<ProbeOutcome>) {
+    for size_index in 0..sizes_amount {
+        let outcome = probe(size_index);
+        if !outcome.is_completed() {
+            return (size_index, Some(outcome));
+        }
     }
+    (sizes_amount, None)
+}
+
+// Only running out of time or memory is expected to repeat on all larger
+// sizes, other failures make the search fall back to the linear one
+fn is_monotonic(outcome: ProbeOutcome) -> bool {
+    matches!(
+        outcome,
+        ProbeOutcome::Completed | ProbeOutcome::TimedOut | ProbeOutcome::OutOfMemory
+    )
 }
 
 impl<'a, 'b, GenArgT, AlgArgT, AlgResT> PartialEq
@@ -226,7 +791,122 @@ impl<'a, 'b, GenArgT, AlgArgT, AlgResT> Hash
 
 pub struct AlgorithmTimeStatistic {
     pub max_size_number: usize,
+    pub iterations_amounts: Vec<u64>,
+    // Outcomes of the probed sizes, None for the ones skipped by the search.
+    // Empty if the threshold is not used
+    pub probe_outcomes: Vec<Option<ProbeOutcome>>,
     pub measures: Vec<Vec<Duration>>,
+    // Amount of samples of every measurement of every size
+    pub measurement_lengths: Vec<Vec<usize>>,
+    // Samples of every metric besides time
+    pub metrics: IndexMap<Metric, Vec<Vec<f64>>>,
+}
+
+impl AlgorithmTimeStatistic {
+    fn push(&mut self, size_index: usize, measurement: SizeMeasurement) {
+        self.measurement_lengths[size_index].push(measurement.times.len());
+        self.measures[size_index].extend(measurement.times);
+        for (metric, value) in measurement.metrics {
+            let max_size_number = self.max_size_number;
+            self.metrics
+                .entry(metric)
+                .or_insert_with(|| vec![vec![]; max_size_number])[size_index]
+                .push(value);
+        }
+    }
+
+    // Drops the failed size and the ones after it
+    fn cut_off(&mut self, size_index: usize, outcome: ProbeOutcome) {
+        self.max_size_number = size_index;
+        self.probe_outcomes.resize(size_index, None);
+        self.probe_outcomes.push(Some(outcome));
+        self.iterations_amounts.truncate(size_index);
+        self.measures.truncate(size_index);
+        self.measurement_lengths.truncate(size_index);
+        for samples in self.metrics.values_mut() {
+            samples.truncate(size_index);
+        }
+    }
+
+    pub fn metric_samples(&self, metric: Metric, size_index: usize) -> Vec<f64> {
+        match metric {
+            Metric::Time => Self::nanos(&self.measures[size_index]),
+            _ => match self.metrics.get(&metric) {
+                Some(samples) => samples[size_index].clone(),
+                None => vec![],
+            },
+        }
+    }
+
+    fn nanos(measures: &[Duration]) -> Vec<f64> {
+        measures.iter().map(|x| x.as_nanos() as f64).collect()
+    }
+
+    pub fn medians(&self) -> Vec<Option<Duration>> {
+        self.measures
+            .iter()
+            .map(|measures| {
+                if measures.is_empty() {
+                    return None;
+                }
+                let median = Quartiles::new(&Self::nanos(measures)).median();
+                Some(Duration::from_nanos(median.round() as u64))
+            })
+            .collect()
+    }
+
+    pub fn mean_confidence_intervals(&self) -> Vec<Option<ConfidenceInterval>> {
+        self.measures
+            .iter()
+            .map(|measures| {
+                if measures.is_empty() {
+                    return None;
+                }
+                Some(statistics::mean_confidence_interval(&Self::nanos(measures)))
+            })
+            .collect()
+    }
+
+    pub fn median_confidence_intervals(&self) -> Vec<Option<ConfidenceInterval>> {
+        self.measures
+            .iter()
+            .map(|measures| {
+                if measures.is_empty() {
+                    return None;
+                }
+                Some(statistics::median_confidence_interval(&Self::nanos(
+                    measures,
+                )))
+            })
+            .collect()
+    }
+
+    pub fn outliers(&self) -> Vec<OutlierCounts> {
+        self.measures
+            .iter()
+            .map(|measures| statistics::classify_outliers(&Self::nanos(measures)))
+            .collect()
+    }
+
+    pub fn complexity<GenArgT>(&self, sizes: &[GenArgT]) -> Option<ComplexityFit>
+    where
+        GenArgT: ToPrimitive,
+    {
+        self.complexity_of(&sizes.iter().map(|size| size.to_f64()).collect::<Vec<_>>())
+    }
+
+    // Sizes as numbers, None for a size that is not a number
+    fn complexity_of(&self, sizes: &[Option<f64>]) -> Option<ComplexityFit> {
+        let mut xs = vec![];
+        let mut ys = vec![];
+        for (size, median) in sizes.iter().zip(self.medians()) {
+            if let Some(median) = median {
+                xs.push((*size)?);
+                ys.push(median.as_nanos() as f64);
+            }
+        }
+        complexity::best_fit(&xs, &ys)
+    }
 }
 
 pub struct PackMeasures<'a, 'b, 'c, GenArgT, AlgArgT, AlgResT>
@@ -237,12 +917,38 @@ where
     description: String,
     filename: String,
     sizes: &'b Vec<GenArgT>,
-    timer: TimerType,
+    timer_name: String,
+    measure_fn: MeasureFn<'a, 'c, GenArgT, AlgArgT, AlgResT>,
+    calibrate_fn: CalibrateFn<'a, 'c, GenArgT, AlgArgT, AlgResT>,
     x_label: String,
     y_label: String,
+    second_size_label: Option<String>,
     iterations_amount: u64,
+    min_batch_time: Option<Duration>,
     use_threshold: bool,
     threshold: Duration,
+    memory_threshold: Option<u64>,
+    threshold_search: ThresholdSearch,
+    outlier_policy: OutlierPolicy,
+    warm_up: WarmUp,
+    sampling_mode: SamplingMode,
+    process_isolation: bool,
+    execution_order: ExecutionOrder,
+    generator: Option<PackGenerator<'c, GenArgT, AlgArgT>>,
+    verification: Option<Verification<'a, AlgArgT, AlgResT>>,
+    need_verification: bool,
+    cpu_affinity: Vec<usize>,
+    // Some measurement has run without the requested affinity
+    affinity_failed: bool,
+    sizes_spec: Option<SizesSpec>,
+    // Load average before the first measurement
+    load_average_start: Option<[f64; 3]>,
+    checkpointing: bool,
+    checkpoint: Option<CheckpointWriter>,
+    // Measurements read from the checkpoint: algorithm, measurement, size index
+    resumed_measurements: HashMap<(String, u64, usize), SizeMeasurement>,
+    // Measurements started since the last write
+    measurements_count: u64,
     time_statistics: IndexMap<
         &'a MeasurableAlgorithm<'a, 'c, GenArgT, AlgArgT, AlgResT>,
         AlgorithmTimeStatistic,
@@ -257,12 +963,34 @@ impl<'a, 'b, 'c, GenArgT, AlgArgT, AlgResT> PackMeasures<'a, 'b, 'c, GenArgT, Al
             description: name.to_string(),
             filename: name.to_string(),
             sizes,
-            timer: TimerType::ProcessTimer,
+            timer_name: ProcessTime::name(),
+            measure_fn: MeasurableAlgorithm::measure::<ProcessTime>,
+            calibrate_fn: MeasurableAlgorithm::calibrate_iterations::<ProcessTime>,
             x_label: String::from_str("Аргументы функций").unwrap(),
             y_label: String::from_str("Значения функций").unwrap(),
+            second_size_label: None,
             iterations_amount: 5,
+            min_batch_time: None,
             use_threshold: false,
             threshold: Duration::new(1, 0),
+            memory_threshold: None,
+            threshold_search: ThresholdSearch::Linear,
+            outlier_policy: OutlierPolicy::Keep,
+            warm_up: WarmUp::Disabled,
+            sampling_mode: SamplingMode::Batch,
+            process_isolation: false,
+            execution_order: ExecutionOrder::Sequential,
+            generator: None,
+            verification: None,
+            need_verification: false,
+            cpu_affinity: vec![],
+            affinity_failed: false,
+            sizes_spec: None,
+            load_average_start: None,
+            checkpointing: false,
+            checkpoint: None,
+            resumed_measurements: HashMap::new(),
+            measurements_count: 0,
             time_statistics: IndexMap::new(),
             need_max_sizes_update: true,
         }
@@ -273,8 +1001,21 @@ impl<'a, 'b, 'c, GenArgT, AlgArgT, AlgResT> PackMeasures<'a, 'b, 'c, GenArgT, Al
         self
     }
 
-    pub fn with_timer(mut self, timer: TimerType) -> Self {
-        self.timer = timer;
+    pub fn with_timer(self, timer: TimerType) -> Self {
+        match timer {
+            TimerType::ProcessTimer => self.with_custom_timer::<ProcessTime>(),
+            TimerType::ThreadTimer => self.with_custom_timer::<ThreadTime>(),
+            TimerType::SystemTimer => self.with_custom_timer::<SystemTime>(),
+        }
+    }
+
+    pub fn with_custom_timer<TimerT>(mut self) -> Self
+    where
+        TimerT: Timer,
+    {
+        self.timer_name = TimerT::name();
+        self.measure_fn = MeasurableAlgorithm::measure::<TimerT>;
+        self.calibrate_fn = MeasurableAlgorithm::calibrate_iterations::<TimerT>;
         self
     }
 
@@ -288,16 +1029,117 @@ impl<'a, 'b, 'c, GenArgT, AlgArgT, AlgResT> PackMeasures<'a, 'b, 'c, GenArgT, Al
         self
     }
 
+    // Axis label of the second size of GridSize sizes
+    pub fn with_second_size_label(mut self, second_size_label: &str) -> Self {
+        self.second_size_label = Some(second_size_label.to_string());
+        self
+    }
+
     pub fn with_iterations_amount(mut self, iterations_amount: u64) -> Self {
         self.iterations_amount = iterations_amount;
         self
     }
 
+    pub fn with_auto_iterations(mut self, min_batch_time: Duration) -> Self {
+        self.min_batch_time = Some(min_batch_time);
+        self
+    }
+
     pub fn with_threshold(mut self, threshold: Duration) -> Self {
         self.threshold = threshold;
         self
     }
 
+    pub fn with_outlier_policy(mut self, outlier_policy: OutlierPolicy) -> Self {
+        self.outlier_policy = outlier_policy;
+        self
+    }
+
+    pub fn with_warm_up(mut self, warm_up: WarmUp) -> Self {
+        self.warm_up = warm_up;
+        self
+    }
+
+    pub fn with_sampling_mode(mut self, sampling_mode: SamplingMode) -> Self {
+        self.sampling_mode = sampling_mode;
+        self
+    }
+
+    // Every measurement of every size runs in its own forked process
+    pub fn with_process_isolation(mut self, process_isolation: bool) -> Self {
+        self.process_isolation = process_isolation;
+        self
+    }
+
+    pub fn with_execution_order(mut self, execution_order: ExecutionOrder) -> Self {
+        self.execution_order = execution_order;
+        self
+    }
+
+    // Input of every timed iteration of every size and measurement is
+    // generated once and all targets run on its clones instead of the inputs
+    // of their own generators. The targets of a size and measurement are
+    // measured back-to-back whatever the execution order
+    pub fn with_generator(mut self, generator: Generator<'c, GenArgT, AlgArgT>) -> Self
+    where
+        AlgArgT: Clone,
+    {
+        self.generator = Some(PackGenerator {
+            generator: RefCell::new(generator),
+            clone_input: AlgArgT::clone,
+            input_key: None,
+            inputs: vec![],
+        });
+        self
+    }
+
+    // Before the measurements every target runs on the same inputs and its
+    // results are compared with the ones of the first target
+    pub fn with_verification<F>(mut self, comparator: F) -> Self
+    where
+        AlgArgT: Clone,
+        F: Fn(&(AlgArgT, AlgResT), &(AlgArgT, AlgResT)) -> bool + 'a,
+    {
+        self.verification = Some(Verification {
+            comparator: Box::new(comparator),
+            clone_input: AlgArgT::clone,
+        });
+        self.need_verification = true;
+        self
+    }
+
+    pub fn with_equality_verification(self) -> Self
+    where
+        AlgArgT: Clone + PartialEq,
+        AlgResT: PartialEq,
+    {
+        self.with_verification(|reference, target| reference == target)
+    }
+
+    // Every finished measurement is appended to a checkpoint file, an
+    // interrupted run of the same pack continues from it
+    pub fn with_checkpointing(mut self, checkpointing: bool) -> Self {
+        self.checkpointing = checkpointing;
+        self
+    }
+
+    // Measurements and probes run only on the given CPUs
+    pub fn with_cpu_affinity(mut self, cpus: &[usize]) -> Self {
+        self.cpu_affinity = cpus.to_vec();
+        self
+    }
+
+    // Address space limit in bytes of the process probing the sizes
+    pub fn with_memory_threshold(mut self, memory_threshold: u64) -> Self {
+        self.memory_threshold = Some(memory_threshold);
+        self
+    }
+
+    pub fn with_threshold_search(mut self, threshold_search: ThresholdSearch) -> Self {
+        self.threshold_search = threshold_search;
+        self
+    }
+
     pub fn set_threshold(&mut self, threshold: Duration) {
         self.threshold = threshold;
     }
@@ -314,7 +1156,11 @@ impl<'a, 'b, 'c, GenArgT, AlgArgT, AlgResT> PackMeasures<'a, 'b, 'c, GenArgT, Al
             measurable_algorithm,
             AlgorithmTimeStatistic {
                 max_size_number: 0,
+                iterations_amounts: vec![],
+                probe_outcomes: vec![],
                 measures: vec![],
+                measurement_lengths: vec![],
+                metrics: IndexMap::new(),
             },
         );
     }
@@ -324,63 +1170,548 @@ impl<'a, 'b, 'c, GenArgT, AlgArgT, AlgResT> PackMeasures<'a, 'b, 'c, GenArgT, Al
 where
     GenArgT: std::fmt::Display + Clone,
 {
+    // Recipe the sizes were built with, written to the description. An
+    // invalid spec or one building other sizes than the ones of the pack is
+    // not recorded
+    pub fn with_sizes_spec(mut self, sizes_spec: &SizesSpec) -> Self {
+        let spec_sizes = match sizes_spec {
+            SizesSpec::Grid { .. } => sizes_spec.build_grid().map(|sizes| {
+                sizes
+                    .iter()
+                    .map(|size| size.to_string())
+                    .collect::<Vec<String>>()
+            }),
+            _ => sizes_spec.build::<usize>().map(|sizes| {
+                sizes
+                    .iter()
+                    .map(|size| size.to_string())
+                    .collect::<Vec<String>>()
+            }),
+        };
+        let spec_sizes = match spec_sizes {
+            Ok(spec_sizes) => spec_sizes,
+            Err(e) => {
+                eprintln!("{}, описание не записывается", e);
+                return self;
+            }
+        };
+        if spec_sizes
+            .into_iter()
+            .eq(self.sizes.iter().map(|size| size.to_string()))
+        {
+            self.sizes_spec = Some(sizes_spec.clone());
+        } else {
+            eprintln!("Ошибка: размеры набора не совпадают с построенными по описанию, описание не записывается");
+        }
+        self
+    }
+
     pub fn measure(&mut self, measures_amount: u64) {
         use std::io::{stdout, Write};
+        let affinity = AffinityGuard::pin(&self.cpu_affinity);
+        if !self.cpu_affinity.is_empty() && !affinity.is_pinned() {
+            self.affinity_failed = true;
+        }
+        if self.load_average_start.is_none() {
+            self.load_average_start = environment::load_average();
+        }
+        if self.checkpointing && self.checkpoint.is_none() {
+            self.open_checkpoint();
+        }
+        let mut prepared = false;
         if self.need_max_sizes_update {
             self.calculate_max_data_sizes();
             self.need_max_sizes_update = false;
+            prepared = true;
+        }
+        if self
+            .time_statistics
+            .values()
+            .any(|statistic| statistic.iterations_amounts.len() != statistic.max_size_number)
+        {
+            self.calculate_iterations_amounts();
+            prepared = true;
+        }
+        if prepared {
+            if let Some(checkpoint) = self.checkpoint.as_mut() {
+                for (algorithm, statistic) in self.time_statistics.iter() {
+                    let entry = CheckpointEntry::Algorithm {
+                        filename: algorithm.filename.clone(),
+                        max_size_number: statistic.max_size_number,
+                        probe_outcomes: statistic.probe_outcomes.clone(),
+                        iterations_amounts: statistic.iterations_amounts.clone(),
+                    };
+                    if let Err(e) = checkpoint.append(&entry) {
+                        eprintln!("Ошибка при записи контрольной точки: {}", e);
+                    }
+                }
+            }
         }
+        if self.need_verification {
+            self.need_verification = false;
+            println!("Проверка результатов");
+            let mismatches = self.verify();
+            for mismatch in mismatches.iter() {
+                println!("{}", mismatch);
+            }
+            if mismatches.is_empty() {
+                println!("Результаты совпадают\n");
+            } else {
+                println!();
+            }
+        }
+        let settings = MeasureSettings {
+            warm_up: self.warm_up,
+            sampling_mode: self.sampling_mode,
+            process_isolation: self.process_isolation,
+            threshold: (self.use_threshold && !self.is_grid()).then_some(self.threshold),
+            memory_threshold: self.memory_threshold,
+        };
         println!("Замер времени выполнения ({})", self.description);
         let time = std::time::Instant::now();
-        for (algorithm, statistic) in self.time_statistics.iter_mut() {
-            println!("Алгоритм: {}", algorithm.description);
-            let mut lock = stdout().lock();
-            for i in 0..measures_amount {
+        let measurements_offset = self.measurements_count;
+        self.measurements_count += measures_amount;
+        let mut lock = stdout().lock();
+        let mut current_algorithm = None;
+        let mut current_measurement = None;
+        for (algorithm_index, i, size_index) in
+            self.measure_tasks(measurements_offset, measures_amount)
+        {
+            let (algorithm, statistic) =
+                self.time_statistics.get_index_mut(algorithm_index).unwrap();
+            let measurement = measurements_offset + i;
+            // Targets of a size and measurement run back-to-back, the shared
+            // inputs of the previous ones are no longer needed
+            if let Some(pack_generator) = self.generator.as_mut() {
+                if pack_generator.input_key != Some((size_index, measurement)) {
+                    pack_generator.input_key = Some((size_index, measurement));
+                    pack_generator.inputs.clear();
+                }
+            }
+            // The target has failed on a smaller size during this run
+            if size_index >= statistic.max_size_number {
+                continue;
+            }
+            if self.execution_order == ExecutionOrder::Sequential
+                && self.generator.is_none()
+                && current_algorithm != Some(algorithm_index)
+            {
+                if current_algorithm.is_some() {
+                    println!();
+                }
+                println!("Алгоритм: {}", algorithm.description);
+                current_algorithm = Some(algorithm_index);
+                current_measurement = None;
+            }
+            if current_measurement != Some(i) {
                 write!(lock, "Номер замера: {}/{}\t\r", i + 1, measures_amount).unwrap();
                 _ = std::io::stdout().flush();
-                let measure_sizes = &self.sizes[0..statistic.max_size_number];
-                let elapsed_time_for_sizes = match &self.timer {
-                    TimerType::ProcessTimer => {
-                        algorithm.measure::<ProcessTime>(measure_sizes, self.iterations_amount)
+                current_measurement = Some(i);
+            }
+            let key = (algorithm.filename.clone(), measurement, size_index);
+            let samples = match self.resumed_measurements.remove(&key) {
+                Some(samples) => samples,
+                None => {
+                    let sizes = &self.sizes[size_index..=size_index];
+                    let iterations_amounts = &statistic.iterations_amounts[size_index..=size_index];
+                    let samples = match self.generator.as_mut() {
+                        Some(pack_generator) => {
+                            let PackGenerator {
+                                generator,
+                                clone_input,
+                                inputs,
+                                ..
+                            } = pack_generator;
+                            let generator = &*generator;
+                            // Every timed iteration gets its own input, the n-th
+                            // call of every target gets a copy of the same one.
+                            // Warm-up inputs are not shared, as the amount of
+                            // warm-up calls may differ between targets
+                            let mut iteration = 0;
+                            (self.measure_fn)(
+                                algorithm,
+                                &mut |size: &GenArgT| {
+                                    if iteration == inputs.len() {
+                                        inputs.push((generator.borrow_mut())(size));
+                                    }
+                                    iteration += 1;
+                                    clone_input(&inputs[iteration - 1])
+                                },
+                                Some(&mut |size: &GenArgT| (generator.borrow_mut())(size)),
+                                sizes,
+                                iterations_amounts,
+                                settings,
+                            )
+                        }
+                        None => (self.measure_fn)(
+                            algorithm,
+                            &mut **algorithm.generator.borrow_mut(),
+                            None,
+                            sizes,
+                            iterations_amounts,
+                            settings,
+                        ),
                     }
-                    TimerType::ThreadTimer => {
-                        algorithm.measure::<ThreadTime>(measure_sizes, self.iterations_amount)
+                    .pop()
+                    .unwrap_or_else(|| Ok(SizeMeasurement::default()));
+                    let samples = match samples {
+                        Ok(samples) => samples,
+                        // Larger sizes are not measured, as if the threshold
+                        // had been exceeded on this one
+                        Err(outcome) => {
+                            eprintln!("\nРазмер {}: {}", self.sizes[size_index], outcome);
+                            statistic.cut_off(size_index, outcome);
+                            if let Some(checkpoint) = self.checkpoint.as_mut() {
+                                let entry = CheckpointEntry::Algorithm {
+                                    filename: algorithm.filename.clone(),
+                                    max_size_number: statistic.max_size_number,
+                                    probe_outcomes: statistic.probe_outcomes.clone(),
+                                    iterations_amounts: statistic.iterations_amounts.clone(),
+                                };
+                                if let Err(e) = checkpoint.append(&entry) {
+                                    eprintln!("Ошибка при записи контрольной точки: {}", e);
+                                }
+                            }
+                            continue;
+                        }
+                    };
+                    if let Some(checkpoint) = self.checkpoint.as_mut() {
+                        let entry = CheckpointEntry::Measurement {
+                            filename: algorithm.filename.clone(),
+                            measurement,
+                            size_index,
+                            samples: samples.clone(),
+                        };
+                        if let Err(e) = checkpoint.append(&entry) {
+                            eprintln!("Ошибка при записи контрольной точки: {}", e);
+                        }
                     }
-                    TimerType::SystemTimer => {
-                        algorithm.measure::<SystemTime>(measure_sizes, self.iterations_amount)
+                    samples
+                }
+            };
+            statistic.push(size_index, samples);
+        }
+        if let Some(pack_generator) = self.generator.as_mut() {
+            pack_generator.input_key = None;
+            pack_generator.inputs.clear();
+        }
+        println!();
+        let took = time.elapsed();
+        println!("Замер занял {:.3}с\n", took.as_secs_f64());
+    }
+
+    // Mismatches with the results of the first target on every size both
+    // targets are measured on
+    pub fn verify(&self) -> Vec<ResultMismatch> {
+        let verification = match &self.verification {
+            Some(verification) => verification,
+            None => return vec![],
+        };
+        let mut targets = self.time_statistics.iter();
+        let (reference, reference_statistic) = match targets.next() {
+            Some(reference) => reference,
+            None => return vec![],
+        };
+        let mut mismatches = vec![];
+        for (size_index, size) in self
+            .sizes
+            .iter()
+            .enumerate()
+            .take(reference_statistic.max_size_number)
+        {
+            let data = match &self.generator {
+                Some(pack_generator) => (pack_generator.generator.borrow_mut())(size),
+                None => (reference.generator.borrow_mut())(size),
+            };
+            let reference_result = reference.run_with_result((verification.clone_input)(&data));
+            for (target, statistic) in self.time_statistics.iter().skip(1) {
+                if size_index >= statistic.max_size_number {
+                    continue;
+                }
+                let target_result = target.run_with_result((verification.clone_input)(&data));
+                if !(verification.comparator)(&reference_result, &target_result) {
+                    mismatches.push(ResultMismatch {
+                        size: size.to_string(),
+                        reference: reference.filename.clone(),
+                        target: target.filename.clone(),
+                    });
+                }
+            }
+        }
+        mismatches
+    }
+
+    // Algorithm index, repetition and size index of every measurement in
+    // the order of execution. With a pack generator all targets of a size and
+    // repetition run back-to-back, so only their shared inputs are kept
+    fn measure_tasks(
+        &self,
+        measurements_offset: u64,
+        measures_amount: u64,
+    ) -> Vec<(usize, u64, usize)> {
+        let max_size_numbers = self
+            .time_statistics
+            .values()
+            .map(|statistic| statistic.max_size_number)
+            .collect::<Vec<usize>>();
+        let shared_inputs = self.generator.is_some();
+        let mut tasks = vec![];
+        match self.execution_order {
+            ExecutionOrder::Sequential if !shared_inputs => {
+                for (algorithm_index, max_size_number) in max_size_numbers.iter().enumerate() {
+                    for i in 0..measures_amount {
+                        for size_index in 0..*max_size_number {
+                            tasks.push((algorithm_index, i, size_index));
+                        }
                     }
-                };
-                for (i, time_elapsed) in elapsed_time_for_sizes.into_iter().enumerate() {
-                    statistic.measures[i].push(time_elapsed);
                 }
             }
-            println!();
+            _ => {
+                let sizes_amount = max_size_numbers.iter().copied().max().unwrap_or(0);
+                for i in 0..measures_amount {
+                    let mut size_tasks = vec![];
+                    for size_index in 0..sizes_amount {
+                        let mut algorithm_tasks = vec![];
+                        for (algorithm_index, max_size_number) in
+                            max_size_numbers.iter().enumerate()
+                        {
+                            if size_index < *max_size_number {
+                                algorithm_tasks.push((algorithm_index, i, size_index));
+                            }
+                        }
+                        size_tasks.push(algorithm_tasks);
+                    }
+                    // Every repetition is shuffled with its own seed, so the
+                    // order does not depend on interruptions of the run
+                    if let ExecutionOrder::Randomized(seed) = self.execution_order {
+                        let measurement = measurements_offset + i;
+                        let mut rng = ChaCha8Rng::seed_from_u64(seed.wrapping_add(measurement));
+                        if shared_inputs {
+                            for algorithm_tasks in size_tasks.iter_mut() {
+                                algorithm_tasks.shuffle(&mut rng);
+                            }
+                            size_tasks.shuffle(&mut rng);
+                        } else {
+                            let mut repetition_tasks = size_tasks.concat();
+                            repetition_tasks.shuffle(&mut rng);
+                            size_tasks = vec![repetition_tasks];
+                        }
+                    }
+                    tasks.extend(size_tasks.into_iter().flatten());
+                }
+            }
+        }
+        tasks
+    }
+
+    pub fn calculate_iterations_amounts(&mut self) {
+        let _affinity = AffinityGuard::pin(&self.cpu_affinity);
+        let min_batch_time = match self.min_batch_time {
+            Some(min_batch_time) => min_batch_time,
+            None => {
+                for statistic in self.time_statistics.values_mut() {
+                    statistic.iterations_amounts =
+                        vec![self.iterations_amount; statistic.max_size_number];
+                }
+                return;
+            }
+        };
+        println!("Подбор количества итераций");
+        let time = std::time::Instant::now();
+        for (algorithm, statistic) in self.time_statistics.iter_mut() {
+            let measure_sizes = &self.sizes[0..statistic.max_size_number];
+            let mut generator = match &self.generator {
+                Some(pack_generator) => pack_generator.generator.borrow_mut(),
+                None => algorithm.generator.borrow_mut(),
+            };
+            statistic.iterations_amounts =
+                (self.calibrate_fn)(algorithm, &mut **generator, measure_sizes, min_batch_time);
         }
         let took = time.elapsed();
-        println!("Замер занял {:.3}с\n", took.as_secs_f64());
+        println!("Подбор занял {:.3}с\n", took.as_secs_f64());
+    }
+
+    // Sizes are probed under the time threshold if it is used and under the
+    // memory threshold if it is set
+    // Sizes of two parameters are ordered by the first one and then by the
+    // second one, not by the run time, so the threshold does not apply to them
+    fn is_grid(&self) -> bool {
+        self.sizes
+            .iter()
+            .any(|size| size.to_string().contains(sizes::GRID_SEPARATOR))
     }
 
     pub fn calculate_max_data_sizes(&mut self) {
-        if self.use_threshold {
+        let _affinity = AffinityGuard::pin(&self.cpu_affinity);
+        let is_grid = self.is_grid();
+        if is_grid && (self.use_threshold || self.memory_threshold.is_some()) {
+            eprintln!("Предупреждение: для двумерных размеров максимальные размеры не вычисляются, ограничение времени не применяется");
+        }
+        if !is_grid && (self.use_threshold || self.memory_threshold.is_some()) {
             println!("Расчёт максимальных размеров");
             let time = std::time::Instant::now();
             for (algorithm, statistics) in self.time_statistics.iter_mut() {
-                statistics.max_size_number =
-                    algorithm.calculate_max_data_size(&self.sizes, self.threshold);
+                let mut generator = match &self.generator {
+                    Some(pack_generator) => pack_generator.generator.borrow_mut(),
+                    None => algorithm.generator.borrow_mut(),
+                };
+                statistics.probe_outcomes = algorithm.calculate_max_data_size(
+                    &mut **generator,
+                    self.sizes,
+                    self.use_threshold.then_some(self.threshold),
+                    self.memory_threshold,
+                    self.threshold_search,
+                );
+                statistics.max_size_number = statistics
+                    .probe_outcomes
+                    .iter()
+                    .take_while(|outcome| outcome.is_none_or(|outcome| outcome.is_completed()))
+                    .count();
             }
             let took = time.elapsed();
             println!("Расчёт занял {:.3}с\n", took.as_secs_f64());
         } else {
             for (_, statistics) in self.time_statistics.iter_mut() {
                 statistics.max_size_number = self.sizes.len();
+                statistics.probe_outcomes.clear();
             }
         }
+        self.resize_statistics();
+    }
+
+    fn resize_statistics(&mut self) {
         for statistics in self.time_statistics.values_mut() {
             statistics
                 .measures
                 .resize(statistics.max_size_number, vec![]);
+            statistics
+                .measurement_lengths
+                .resize(statistics.max_size_number, vec![]);
+            for samples in statistics.metrics.values_mut() {
+                samples.resize(statistics.max_size_number, vec![]);
+            }
         }
     }
+
+    fn checkpoint_path(&self) -> PathBuf {
+        PathBuf::from(format!(
+            "{}/{}/{}",
+            PACKS_DIR, self.filename, CHECKPOINT_FILE
+        ))
+    }
+
+    fn checkpoint_pack(&self) -> CheckpointPack {
+        CheckpointPack {
+            filename: self.filename.clone(),
+            sizes: self.sizes.iter().map(|x| x.to_string()).collect(),
+            algorithms: self
+                .time_statistics
+                .keys()
+                .map(|x| x.filename.clone())
+                .collect(),
+            timer: self.timer_name.clone(),
+            iterations_amount: self.iterations_amount,
+            min_batch_time: self.min_batch_time,
+            use_threshold: self.use_threshold,
+            threshold: self.threshold,
+            memory_threshold: self.memory_threshold,
+            threshold_search: self.threshold_search,
+            warm_up: self.warm_up,
+            sampling_mode: self.sampling_mode,
+            process_isolation: self.process_isolation,
+            execution_order: self.execution_order,
+            cpu_affinity: self.cpu_affinity.clone(),
+            shared_generator: self.generator.is_some(),
+        }
+    }
+
+    // Restores the state saved in the checkpoint of the same pack. The
+    // checkpoint is rewritten without a line cut by the interruption
+    fn open_checkpoint(&mut self) {
+        let path = self.checkpoint_path();
+        let pack = self.checkpoint_pack();
+        let entries = match checkpoint::read(&path, &pack) {
+            Ok(entries) => entries,
+            Err(e) => {
+                eprintln!("Ошибка при чтении контрольной точки: {}", e);
+                vec![]
+            }
+        };
+        let mut writer = match CheckpointWriter::create(&path, &pack) {
+            Ok(writer) => writer,
+            Err(e) => {
+                eprintln!("Ошибка при создании контрольной точки: {}", e);
+                return;
+            }
+        };
+        // Filenames of the targets with a restored maximal size, a target
+        // cut off during the run has several entries
+        let mut prepared_algorithms = HashSet::new();
+        for entry in entries {
+            if let Err(e) = writer.append(&entry) {
+                eprintln!("Ошибка при записи контрольной точки: {}", e);
+            }
+            match entry {
+                CheckpointEntry::Pack(_) => {}
+                CheckpointEntry::Algorithm {
+                    filename,
+                    max_size_number,
+                    probe_outcomes,
+                    iterations_amounts,
+                } => {
+                    if let Some((_, statistic)) = self
+                        .time_statistics
+                        .iter_mut()
+                        .find(|(algorithm, _)| algorithm.filename == filename)
+                    {
+                        statistic.max_size_number = max_size_number;
+                        statistic.probe_outcomes = probe_outcomes;
+                        statistic.iterations_amounts = iterations_amounts;
+                        prepared_algorithms.insert(filename);
+                    }
+                }
+                CheckpointEntry::Measurement {
+                    filename,
+                    measurement,
+                    size_index,
+                    samples,
+                } => {
+                    self.resumed_measurements
+                        .insert((filename, measurement, size_index), samples);
+                }
+            }
+        }
+        if !prepared_algorithms.is_empty()
+            && prepared_algorithms.len() == self.time_statistics.len()
+        {
+            self.need_max_sizes_update = false;
+            self.resize_statistics();
+        }
+        if !self.resumed_measurements.is_empty() {
+            println!(
+                "Продолжение прерванного замера, восстановлено результатов: {}",
+                self.resumed_measurements.len()
+            );
+        }
+        self.checkpoint = Some(writer);
+    }
+}
+
+impl<'a, 'b, 'c, GenArgT, AlgArgT, AlgResT> PackMeasures<'a, 'b, 'c, GenArgT, AlgArgT, AlgResT>
+where
+    GenArgT: ToPrimitive,
+{
+    pub fn complexity(&self) -> IndexMap<String, Option<ComplexityFit>> {
+        self.time_statistics
+            .iter()
+            .map(|(algorithm, statistic)| {
+                (
+                    algorithm.filename.clone(),
+                    statistic.complexity(&self.sizes[0..statistic.max_size_number]),
+                )
+            })
+            .collect()
+    }
 }
 
 impl<'a, 'b, 'c, GenArgT, AlgArgT, AlgResT> PackMeasures<'a, 'b, 'c, GenArgT, AlgArgT, AlgResT>
@@ -388,11 +1719,20 @@ where
     GenArgT: std::fmt::Display + Clone + serde::ser::Serialize,
 {
     pub fn write(&self) -> Result<(), Box<dyn std::error::Error>> {
-        use std::io::Write;
         let data_path =
             PathBuf::from_str(format!("{}/{}/{}", PACKS_DIR, self.filename, DATA_DIR).as_str())?;
+        let pack_description_file_path = PathBuf::from_str(
+            format!("{}/{}/{}", PACKS_DIR, self.filename, DESCRIPTION_FILE).as_str(),
+        )?;
         if !data_path.is_dir() {
-            fs::create_dir_all(data_path)?;
+            fs::create_dir_all(&data_path)?;
+        } else if pack_description_file_path.is_file() {
+            // Samples are appended to the existing files, so older samples
+            // have to be stored in the same unit
+            let stored_unit = description::read_time_unit(&pack_description_file_path)?;
+            if stored_unit != TimeUnit::Nanoseconds {
+                rescale_samples(&data_path, stored_unit.convert(1.0, TimeUnit::Nanoseconds))?;
+            }
         }
         let mut descriptions = vec![];
         for (algorithm, statistic) in self.time_statistics.iter() {
@@ -400,6 +1740,8 @@ where
                 filename: algorithm.filename.clone(),
                 description: algorithm.description.clone(),
                 max_size_number: statistic.max_size_number,
+                iterations_amounts: statistic.iterations_amounts.clone(),
+                probe_outcomes: statistic.probe_outcomes.clone(),
             };
             descriptions.push(target_description);
         }
@@ -409,8 +1751,28 @@ where
             sizes: self.sizes.clone(),
             x_label: self.x_label.clone(),
             y_label: self.y_label.clone(),
+            second_size_label: self.second_size_label.clone(),
+            timer: Some(self.timer_name.clone()),
+            time_unit: TimeUnit::Nanoseconds,
             iterations_amount: self.iterations_amount,
+            min_batch_time: self.min_batch_time,
             threshold: self.threshold,
+            memory_threshold: self.memory_threshold,
+            threshold_search: self.threshold_search,
+            outlier_policy: self.outlier_policy,
+            warm_up: self.warm_up,
+            sampling_mode: self.sampling_mode,
+            process_isolation: self.process_isolation,
+            execution_order: self.execution_order,
+            shared_generator: self.generator.is_some(),
+            // Only the CPUs the measurements were actually pinned to
+            cpu_affinity: if self.affinity_failed {
+                vec![]
+            } else {
+                self.cpu_affinity.clone()
+            },
+            sizes_spec: self.sizes_spec.clone(),
+            environment: Some(Environment::capture(self.load_average_start)),
             target_descriptions: descriptions,
         };
         let pack_description_dir_path =
@@ -420,13 +1782,75 @@ where
         //     pack_description_file_path.as_os_str().to_str().unwrap()
         // );
         pack_description.write(&pack_description_dir_path)?;
+        self.write_metrics(format!("{}/{}/{}", PACKS_DIR, self.filename, METRICS_DIR).as_str())?;
+        self.write_samples(format!("{}/{}/{}", PACKS_DIR, self.filename, DATA_DIR).as_str())?;
+        Ok(())
+    }
+
+    // Called after write, so that the written measurements are not resumed
+    // by the next run
+    pub fn finish_checkpoint(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.checkpoint.take().is_some() {
+            fs::remove_file(self.checkpoint_path())?;
+        }
+        self.resumed_measurements.clear();
+        self.measurements_count = 0;
+        Ok(())
+    }
+
+    // Every metric besides time is stored in its own directory, one value per line
+    fn write_metrics(&self, metrics_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        use std::io::Write;
         for (algorithm, statistic) in self.time_statistics.iter() {
-            let relative_path = format!(
-                "{}/{}/{}/{}/",
-                PACKS_DIR, self.filename, DATA_DIR, algorithm.filename
-            );
+            for (metric, samples) in statistic.metrics.iter() {
+                let relative_path = format!(
+                    "{}/{}/{}/",
+                    metrics_path,
+                    metric.dir_name(),
+                    algorithm.filename
+                );
+                fs::create_dir_all(&relative_path)?;
+                for (i, size_samples) in samples.iter().enumerate() {
+                    if size_samples.is_empty() {
+                        continue;
+                    }
+                    let file_path = format!("{}{}.txt", relative_path, self.sizes[i]);
+                    let mut file = fs::OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(&file_path)?;
+                    let res_str = size_samples.iter().fold(String::new(), |mut a, b| {
+                        a.push_str(&b.to_string());
+                        a.push('\n');
+                        a
+                    });
+                    if let Err(e) = file.write(res_str.as_bytes()) {
+                        eprintln!("Ошибка при записи в файл: {}", e);
+                        return Err(e.into());
+                    };
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn write_baseline(&self, baseline_name: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let baseline_path = format!(
+            "{}/{}/{}/{}",
+            PACKS_DIR, self.filename, BASELINES_DIR, baseline_name
+        );
+        if PathBuf::from_str(&baseline_path)?.is_dir() {
+            fs::remove_dir_all(&baseline_path)?;
+        }
+        self.write_samples(&baseline_path)
+    }
+
+    fn write_samples(&self, data_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        use std::io::Write;
+        for (algorithm, statistic) in self.time_statistics.iter() {
+            let relative_path = format!("{}/{}/", data_path, algorithm.filename);
             fs::create_dir_all(&relative_path)
-                .expect(format!("Не удалось создать каталог {}", relative_path).as_str());
+                .unwrap_or_else(|_| panic!("Не удалось создать каталог {}", relative_path));
             for i in 0..statistic.max_size_number {
                 let file_path = format!("{}{}.txt", relative_path, self.sizes[i]);
                 let mut file = fs::OpenOptions::new()
@@ -434,13 +1858,24 @@ where
                     .append(true)
                     .open(&file_path)?;
 
-                let res_str = statistic.measures[i]
-                    .iter()
-                    .fold(String::new(), |mut a, b| {
-                        a.push_str(&b.as_micros().to_string());
-                        a.push('\n');
-                        a
-                    });
+                // Every measurement is written on its own line
+                let measures = &statistic.measures[i];
+                let mut res_str = String::new();
+                let mut start = 0;
+                let lengths = statistic.measurement_lengths.get(i).map_or(&[][..], |x| x);
+                for length in lengths {
+                    let end = (start + length).min(measures.len());
+                    if start == end {
+                        continue;
+                    }
+                    let samples = measures[start..end]
+                        .iter()
+                        .map(|x| (x.as_nanos() as u64).to_string())
+                        .collect::<Vec<String>>();
+                    res_str.push_str(&samples.join(" "));
+                    res_str.push('\n');
+                    start = end;
+                }
 
                 if let Err(e) = file.write(res_str.as_bytes()) {
                     eprintln!("Ошибка при записи в файл: {}", e);
@@ -451,13 +1886,59 @@ where
         Ok(())
     }
 
+    pub fn current_samples(&self) -> PackSamples {
+        let mut pack_samples = PackSamples::new();
+        for (algorithm, statistic) in self.time_statistics.iter() {
+            let size_samples = (0..statistic.max_size_number)
+                .map(|i| {
+                    let samples = statistic.measures[i]
+                        .iter()
+                        .map(|x| x.as_nanos() as f64)
+                        .collect::<Vec<f64>>();
+                    (self.sizes[i].to_string(), samples)
+                })
+                .collect();
+            pack_samples.insert(algorithm.filename.clone(), size_samples);
+        }
+        pack_samples
+    }
+
+    pub fn compare_with_baseline(
+        &self,
+        baseline_name: &str,
+        significance_level: f64,
+    ) -> Result<PackComparison, Box<dyn std::error::Error>> {
+        let baseline_path = PathBuf::from_str(
+            format!(
+                "{}/{}/{}/{}",
+                PACKS_DIR, self.filename, BASELINES_DIR, baseline_name
+            )
+            .as_str(),
+        )?;
+        let baseline = comparison::read_pack_samples(&baseline_path)?;
+        Ok(comparison::compare_samples(
+            &baseline,
+            &self.current_samples(),
+            significance_level,
+        ))
+    }
+
+    // Time table with the fitted complexity of every target in the last row
     pub fn print(&self) {
+        self.print_table(Metric::Time, true);
+    }
+
+    pub fn print_metric(&self, metric: Metric) {
+        self.print_table(metric, false);
+    }
+
+    fn print_table(&self, metric: Metric, with_complexity: bool) {
         use prettytable::{format::Alignment, Cell, Row, Table};
         let mut table = Table::new();
         let mut cells: Vec<Cell> = Vec::new();
         cells.push(Cell::new(&self.x_label));
         for algorithm in self.time_statistics.keys() {
-            cells.push(Cell::new(&algorithm.description.as_str()));
+            cells.push(Cell::new(algorithm.description.as_str()));
         }
         table.add_row(Row::new(vec![Cell::new_align(
             self.filename.as_str(),
@@ -471,22 +1952,213 @@ where
             cells.push(Cell::new(self.sizes[i].to_string().as_str()));
             for statistic in self.time_statistics.values() {
                 let time_str: String = if i < statistic.max_size_number {
-                    let mean_time_elapsed = statistic.measures[i]
-                        .iter()
-                        .fold(Duration::new(0, 0), |acc, e| acc + *e)
-                        .checked_div(statistic.measures[i].len() as u32)
-                        .unwrap();
-                    format!("{}", mean_time_elapsed.as_nanos())
+                    let samples = statistic.metric_samples(metric, i);
+                    if samples.is_empty() {
+                        String::from("-")
+                    } else {
+                        let samples =
+                            statistics::apply_outlier_policy(&samples, self.outlier_policy);
+                        let mean_interval = statistics::mean_confidence_interval(&samples);
+                        let median_interval = statistics::median_confidence_interval(&samples);
+                        match metric {
+                            Metric::Time => format!(
+                                "{:.0} ± {:.0} / {:.0} ± {:.0}",
+                                mean_interval.estimate,
+                                mean_interval.half_width(),
+                                median_interval.estimate,
+                                median_interval.half_width()
+                            ),
+                            _ => format!(
+                                "{:.2} ± {:.2} / {:.2} ± {:.2}",
+                                mean_interval.estimate,
+                                mean_interval.half_width(),
+                                median_interval.estimate,
+                                median_interval.half_width()
+                            ),
+                        }
+                    }
                 } else {
-                    format!(">{}", self.threshold.as_nanos())
+                    match statistic.probe_outcomes.get(statistic.max_size_number) {
+                        Some(Some(outcome)) if *outcome != ProbeOutcome::TimedOut => {
+                            if i == statistic.max_size_number {
+                                outcome.to_string()
+                            } else {
+                                String::from("-")
+                            }
+                        }
+                        _ if metric == Metric::Time => format!(">{}", self.threshold.as_nanos()),
+                        _ => String::from("-"),
+                    }
                 };
 
                 cells.push(Cell::new(&time_str));
             }
             table.add_row(Row::new(cells));
         }
+        if with_complexity {
+            // Sizes are fitted by their printed values, as the graphs do
+            let size_values = self
+                .sizes
+                .iter()
+                .map(|size| sizes::size_value(&size.to_string()))
+                .collect::<Vec<Option<f64>>>();
+            let mut cells = vec![Cell::new("Асимптотика")];
+            for statistic in self.time_statistics.values() {
+                let fit_str =
+                    match statistic.complexity_of(&size_values[0..statistic.max_size_number]) {
+                        Some(fit) => fit.to_string(),
+                        None => String::from("-"),
+                    };
+                cells.push(Cell::new(&fit_str));
+            }
+            table.add_row(Row::new(cells));
+        }
 
-        println!("{}", self.y_label);
+        // Cells are the mean and the median with the half-widths of their confidence intervals
+        match metric {
+            Metric::Time => println!(
+                "{} ({}): среднее ± ДИ / медиана ± ДИ",
+                self.y_label, self.timer_name
+            ),
+            metric => println!("{}: среднее ± ДИ / медиана ± ДИ", metric),
+        }
+        table.printstd();
+    }
+
+    pub fn print_outliers(&self) {
+        use prettytable::{format::Alignment, Cell, Row, Table};
+        let mut table = Table::new();
+        let mut cells: Vec<Cell> = Vec::new();
+        cells.push(Cell::new(&self.x_label));
+        for algorithm in self.time_statistics.keys() {
+            cells.push(Cell::new(algorithm.description.as_str()));
+        }
+        table.add_row(Row::new(vec![Cell::new_align(
+            self.filename.as_str(),
+            Alignment::CENTER,
+        )
+        .with_hspan(self.time_statistics.keys().len() + 1)]));
+
+        table.add_row(Row::new(cells));
+        let outliers = self
+            .time_statistics
+            .values()
+            .map(|statistic| statistic.outliers())
+            .collect::<Vec<Vec<OutlierCounts>>>();
+        for i in 0..self.sizes.len() {
+            let mut cells: Vec<Cell> = Vec::new();
+            cells.push(Cell::new(self.sizes[i].to_string().as_str()));
+            for algorithm_outliers in outliers.iter() {
+                let outliers_str = match algorithm_outliers.get(i) {
+                    Some(counts) => counts.to_string(),
+                    None => String::from("-"),
+                };
+                cells.push(Cell::new(&outliers_str));
+            }
+            table.add_row(Row::new(cells));
+        }
+
+        println!("Выбросы (умеренные/сильные)");
         table.printstd();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEARCHES: [ThresholdSearch; 3] = [
+        ThresholdSearch::Linear,
+        ThresholdSearch::Galloping,
+        ThresholdSearch::Binary,
+    ];
+
+    // Result of the search with the outcome of every size scripted
+    fn search(
+        outcomes: &[ProbeOutcome],
+        search: ThresholdSearch,
+    ) -> ((usize, Option<ProbeOutcome>), Vec<usize>) {
+        let mut probed = vec![];
+        let result = search_max_size_number(outcomes.len(), search, &mut |size_index| {
+            probed.push(size_index);
+            outcomes[size_index]
+        });
+        (result, probed)
+    }
+
+    // Sizes up to `completed` are completed, the rest have timed out
+    fn threshold_outcomes(sizes_amount: usize, completed: usize) -> Vec<ProbeOutcome> {
+        (0..sizes_amount)
+            .map(|size_index| {
+                if size_index < completed {
+                    ProbeOutcome::Completed
+                } else {
+                    ProbeOutcome::TimedOut
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn all_sizes_completed() {
+        let outcomes = threshold_outcomes(10, 10);
+        for threshold_search in SEARCHES {
+            assert_eq!(search(&outcomes, threshold_search).0, (10, None));
+        }
+    }
+
+    #[test]
+    fn first_size_failed() {
+        let outcomes = threshold_outcomes(10, 0);
+        for threshold_search in SEARCHES {
+            assert_eq!(
+                search(&outcomes, threshold_search).0,
+                (0, Some(ProbeOutcome::TimedOut))
+            );
+        }
+    }
+
+    #[test]
+    fn last_size_failed() {
+        let outcomes = threshold_outcomes(10, 9);
+        for threshold_search in SEARCHES {
+            assert_eq!(
+                search(&outcomes, threshold_search).0,
+                (9, Some(ProbeOutcome::TimedOut))
+            );
+        }
+    }
+
+    #[test]
+    fn every_threshold_is_found() {
+        for completed in 0..=16 {
+            let outcomes = threshold_outcomes(16, completed);
+            let expected = (
+                completed,
+                (completed < 16).then_some(ProbeOutcome::TimedOut),
+            );
+            for threshold_search in SEARCHES {
+                assert_eq!(search(&outcomes, threshold_search).0, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn galloping_probes() {
+        let outcomes = threshold_outcomes(16, 5);
+        let (result, probed) = search(&outcomes, ThresholdSearch::Galloping);
+        assert_eq!(result, (5, Some(ProbeOutcome::TimedOut)));
+        assert_eq!(probed, vec![0, 1, 3, 7, 5, 4]);
+    }
+
+    #[test]
+    fn non_monotonic_failure_falls_back_to_linear() {
+        let mut outcomes = threshold_outcomes(8, 4);
+        outcomes[4] = ProbeOutcome::Panicked;
+        for threshold_search in SEARCHES {
+            let (result, probed) = search(&outcomes, threshold_search);
+            assert_eq!(result, (4, Some(ProbeOutcome::Panicked)));
+            assert!(probed.ends_with(&[0, 1, 2, 3, 4]));
+        }
+    }
+}
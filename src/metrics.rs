@@ -0,0 +1,215 @@
+use crate::allocator::AllocationCounters;
+
+use nix::sys::resource::{getrusage, UsageWho};
+
+use std::fmt;
+use std::fs;
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize,
+)]
+pub enum Metric {
+    #[default]
+    Time,
+    // High-water mark of the resident memory of the process during a
+    // measurement. Linux only: the mark is reset through /proc/self/clear_refs
+    #[serde(alias = "MaxRss", alias = "ProcessPeakRss")]
+    PeakRss,
+    MinorPageFaults,
+    MajorPageFaults,
+    VoluntaryContextSwitches,
+    InvoluntaryContextSwitches,
+    Allocations,
+    AllocatedBytes,
+    PeakLiveBytes,
+}
+
+impl Metric {
+    pub const RESOURCE_USAGE: [Metric; 5] = [
+        Metric::PeakRss,
+        Metric::MinorPageFaults,
+        Metric::MajorPageFaults,
+        Metric::VoluntaryContextSwitches,
+        Metric::InvoluntaryContextSwitches,
+    ];
+
+    // Available only with the counting allocator installed
+    pub const ALLOCATIONS: [Metric; 3] = [
+        Metric::Allocations,
+        Metric::AllocatedBytes,
+        Metric::PeakLiveBytes,
+    ];
+
+    // Highest value of a measurement rather than an average per call
+    pub fn is_peak(&self) -> bool {
+        matches!(self, Self::PeakRss | Self::PeakLiveBytes)
+    }
+
+    // Name of the directory with the samples of the metric
+    pub fn dir_name(&self) -> &'static str {
+        match self {
+            Self::Time => "time",
+            Self::PeakRss => "peak_rss",
+            Self::MinorPageFaults => "minor_page_faults",
+            Self::MajorPageFaults => "major_page_faults",
+            Self::VoluntaryContextSwitches => "voluntary_context_switches",
+            Self::InvoluntaryContextSwitches => "involuntary_context_switches",
+            Self::Allocations => "allocations",
+            Self::AllocatedBytes => "allocated_bytes",
+            Self::PeakLiveBytes => "peak_live_bytes",
+        }
+    }
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let output = match self {
+            Self::Time => "Время работы",
+            Self::PeakRss => "Пиковый размер резидентной памяти процесса за замер, КиБ",
+            Self::MinorPageFaults => "Страничные ошибки без ввода-вывода на вызов",
+            Self::MajorPageFaults => "Страничные ошибки с вводом-выводом на вызов",
+            Self::VoluntaryContextSwitches => "Добровольные переключения контекста на вызов",
+            Self::InvoluntaryContextSwitches => "Принудительные переключения контекста на вызов",
+            Self::Allocations => "Выделения памяти на вызов",
+            Self::AllocatedBytes => "Выделено байт на вызов",
+            Self::PeakLiveBytes => "Пиковый объём занятой памяти, байт",
+        };
+        write!(f, "{}", output)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ResourceUsage {
+    // None if the high-water mark could not be reset or read
+    peak_rss: Option<i64>,
+    minor_page_faults: i64,
+    major_page_faults: i64,
+    voluntary_context_switches: i64,
+    involuntary_context_switches: i64,
+    allocations: Option<AllocationCounters>,
+}
+
+impl ResourceUsage {
+    pub fn now() -> Self {
+        match getrusage(UsageWho::RUSAGE_SELF) {
+            Ok(usage) => Self {
+                peak_rss: read_peak_rss(),
+                minor_page_faults: usage.minor_page_faults(),
+                major_page_faults: usage.major_page_faults(),
+                voluntary_context_switches: usage.voluntary_context_switches(),
+                involuntary_context_switches: usage.involuntary_context_switches(),
+                allocations: AllocationCounters::now(),
+            },
+            Err(_) => Self {
+                peak_rss: read_peak_rss(),
+                allocations: AllocationCounters::now(),
+                ..Self::default()
+            },
+        }
+    }
+
+    // Snapshot at the start of a measurement, peak live bytes are counted from here
+    pub fn start() -> Self {
+        AllocationCounters::reset_peak();
+        let usage = Self::now();
+        Self {
+            peak_rss: usage.peak_rss.filter(|_| reset_peak_rss()),
+            ..usage
+        }
+    }
+
+    // Metrics of `iterations_amount` calls made since `start`: counters are
+    // averaged per call, peak RSS is the high-water mark since `start`
+    pub fn metrics_since(&self, start: &Self, iterations_amount: u64) -> Vec<(Metric, f64)> {
+        let per_call = |end: i64, start: i64| (end - start) as f64 / iterations_amount as f64;
+        let mut metrics = vec![
+            (
+                Metric::MinorPageFaults,
+                per_call(self.minor_page_faults, start.minor_page_faults),
+            ),
+            (
+                Metric::MajorPageFaults,
+                per_call(self.major_page_faults, start.major_page_faults),
+            ),
+            (
+                Metric::VoluntaryContextSwitches,
+                per_call(
+                    self.voluntary_context_switches,
+                    start.voluntary_context_switches,
+                ),
+            ),
+            (
+                Metric::InvoluntaryContextSwitches,
+                per_call(
+                    self.involuntary_context_switches,
+                    start.involuntary_context_switches,
+                ),
+            ),
+        ];
+        if let (Some(peak_rss), Some(_)) = (self.peak_rss, start.peak_rss) {
+            metrics.insert(0, (Metric::PeakRss, peak_rss as f64));
+        }
+        // Calls are made one after another, so the peak above the starting
+        // level is the peak of a single call
+        if let (Some(end), Some(start)) = (self.allocations, start.allocations) {
+            metrics.push((
+                Metric::Allocations,
+                per_call(end.allocations as i64, start.allocations as i64),
+            ));
+            metrics.push((
+                Metric::AllocatedBytes,
+                per_call(end.allocated_bytes as i64, start.allocated_bytes as i64),
+            ));
+            metrics.push((
+                Metric::PeakLiveBytes,
+                end.peak_live_bytes.saturating_sub(start.live_bytes) as f64,
+            ));
+        }
+        metrics
+    }
+}
+
+// Peak resident memory of the process in KiB, VmHWM of /proc/self/status
+fn read_peak_rss() -> Option<i64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()
+}
+
+// Writing 5 to clear_refs sets the high-water mark to the current RSS
+fn reset_peak_rss() -> bool {
+    fs::write("/proc/self/clear_refs", "5").is_ok()
+}
+
+// Metrics of consecutive parts of a measurement with the amounts of their
+// calls: averages are weighted by the calls, peaks are the highest one
+pub fn merge_metrics(parts: &[(Vec<(Metric, f64)>, u64)]) -> Vec<(Metric, f64)> {
+    let total_calls = parts.iter().map(|(_, calls)| *calls).sum::<u64>();
+    let mut merged: Vec<(Metric, f64)> = vec![];
+    for (metrics, calls) in parts {
+        for (metric, value) in metrics {
+            let value = if metric.is_peak() {
+                *value
+            } else {
+                value * *calls as f64 / total_calls as f64
+            };
+            match merged
+                .iter_mut()
+                .find(|(merged_metric, _)| merged_metric == metric)
+            {
+                Some((_, merged_value)) if metric.is_peak() => {
+                    *merged_value = merged_value.max(value)
+                }
+                Some((_, merged_value)) => *merged_value += value,
+                None => merged.push((*metric, value)),
+            }
+        }
+    }
+    merged
+}
@@ -1,25 +1,81 @@
 use crate::measures::Algorithm;
 use nix::errno::Errno;
+#[cfg(target_os = "linux")]
+use nix::poll::{poll, PollFd, PollFlags, PollTimeout};
+use nix::sys::resource::{setrlimit, Resource};
 use nix::sys::signal::{kill, Signal};
 use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
-use nix::unistd::{fork, ForkResult};
+use nix::unistd::{fork, pipe, ForkResult, Pid};
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Write};
+#[cfg(target_os = "linux")]
+use std::os::fd::{AsFd, FromRawFd, RawFd};
+use std::os::fd::{AsRawFd, OwnedFd};
+use std::panic::{catch_unwind, AssertUnwindSafe};
 use std::process::exit;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+// Same code as the one of a panicking Rust process
+const PANIC_EXIT_CODE: i32 = 101;
+const SERIALIZATION_EXIT_CODE: i32 = 102;
+// Printed to stderr by the default handler of a failed allocation before it aborts
+const ALLOCATION_FAILURE_MESSAGE: &str = "memory allocation of";
+// Polling step when the child can not be waited for through a pidfd
+const FALLBACK_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ProbeOutcome {
+    Completed,
+    TimedOut,
+    OutOfMemory,
+    Panicked,
+    Signaled(i32),
+    Exited(i32),
+}
+
+impl ProbeOutcome {
+    pub fn is_completed(&self) -> bool {
+        *self == Self::Completed
+    }
+}
+
+impl fmt::Display for ProbeOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Completed => write!(f, "завершено"),
+            Self::TimedOut => write!(f, "превышено время"),
+            Self::OutOfMemory => write!(f, "нехватка памяти"),
+            Self::Panicked => write!(f, "паника"),
+            Self::Signaled(signal) => match Signal::try_from(*signal) {
+                Ok(signal) => write!(f, "сигнал {}", signal),
+                Err(_) => write!(f, "сигнал {}", signal),
+            },
+            Self::Exited(code) => write!(f, "код возврата {}", code),
+        }
+    }
+}
+
+// The child is killed if it does not finish in `threshold`
 pub unsafe fn call_long_running_function<'a, AlgArgT, ResT>(
     function: &Algorithm<'a, AlgArgT, ResT>,
     mut data: AlgArgT,
-    threshold: Duration,
-) -> bool {
-    let mut result = false;
-
+    threshold: Option<Duration>,
+    memory_limit: Option<u64>,
+) -> ProbeOutcome {
+    let stderr_pipe = stderr_pipe(memory_limit);
     let child_pid = match fork() {
         Ok(ForkResult::Child) => {
-            match function {
+            redirect_stderr(stderr_pipe);
+            limit_memory(memory_limit);
+            let result = catch_unwind(AssertUnwindSafe(|| match function {
                 Algorithm::NonMutatingAlgorithm(function) => _ = function(&data),
                 Algorithm::MutatingAlgorithm(function) => _ = function(&mut data),
+            }));
+            match result {
+                Ok(()) => exit(0),
+                Err(_) => exit(PANIC_EXIT_CODE),
             }
-            exit(0);
         }
 
         Ok(ForkResult::Parent { child, .. }) => child,
@@ -28,36 +84,242 @@ pub unsafe fn call_long_running_function<'a, AlgArgT, ResT>(
             panic!("[call_long_running_function] fork() failed: {}", err);
         }
     };
+    let stderr_reader = read_stderr(stderr_pipe);
 
-    let time = std::time::Instant::now();
-    loop {
-        std::thread::sleep(std::time::Duration::from_secs_f64(0.1));
-        match waitpid(child_pid, Some(WaitPidFlag::WNOHANG)) {
-            Ok(WaitStatus::StillAlive) => {}
+    let outcome = match threshold.and_then(|threshold| Instant::now().checked_add(threshold)) {
+        Some(deadline) => wait_until(child_pid, deadline),
+        None => Some(wait(child_pid)),
+    };
+    if let Some(outcome) = outcome {
+        return memory_outcome(outcome, stderr_reader);
+    }
+    kill_child(child_pid);
+    _ = stderr_reader.map(|reader| reader.join());
+    ProbeOutcome::TimedOut
+}
 
-            Ok(_status) => {
-                result = true;
-                break;
+// Runs `function` in a forked child, the result is sent back over a pipe.
+// The child is killed if it does not finish in `timeout`
+pub unsafe fn call_in_child<F, T>(
+    function: F,
+    timeout: Option<Duration>,
+    memory_limit: Option<u64>,
+) -> Result<T, ProbeOutcome>
+where
+    F: FnOnce() -> T,
+    T: serde::Serialize + serde::de::DeserializeOwned,
+{
+    let (reader, writer) = match pipe() {
+        Ok(fds) => fds,
+        Err(err) => panic!("[call_in_child] pipe() failed: {}", err),
+    };
+    let stderr_pipe = stderr_pipe(memory_limit);
+    let child_pid = match fork() {
+        Ok(ForkResult::Child) => {
+            drop(reader);
+            redirect_stderr(stderr_pipe);
+            limit_memory(memory_limit);
+            match catch_unwind(AssertUnwindSafe(function)) {
+                Ok(result) => match serde_json::to_writer(File::from(writer), &result) {
+                    Ok(()) => exit(0),
+                    Err(_) => exit(SERIALIZATION_EXIT_CODE),
+                },
+                Err(_) => exit(PANIC_EXIT_CODE),
             }
+        }
+
+        Ok(ForkResult::Parent { child, .. }) => child,
+
+        Err(err) => {
+            panic!("[call_in_child] fork() failed: {}", err);
+        }
+    };
+    drop(writer);
+    let stderr_reader = read_stderr(stderr_pipe);
+    // Read while waiting, a large result would not fit into the pipe buffer
+    let output_reader = std::thread::spawn(move || {
+        let mut output = String::new();
+        File::from(reader)
+            .read_to_string(&mut output)
+            .map(|_| output)
+    });
+
+    let outcome = match timeout.and_then(|timeout| Instant::now().checked_add(timeout)) {
+        Some(deadline) => wait_until(child_pid, deadline),
+        None => Some(wait(child_pid)),
+    };
+    let outcome = match outcome {
+        Some(outcome) => memory_outcome(outcome, stderr_reader),
+        None => {
+            kill_child(child_pid);
+            _ = stderr_reader.map(|reader| reader.join());
+            ProbeOutcome::TimedOut
+        }
+    };
+    let read_result = output_reader.join();
+    if !outcome.is_completed() {
+        return Err(outcome);
+    }
+    let output = match read_result {
+        Ok(Ok(output)) => output,
+        Ok(Err(err)) => panic!("[call_in_child] Error reading child output: {}", err),
+        Err(_) => panic!("[call_in_child] Error reading child output"),
+    };
+    match serde_json::from_str(&output) {
+        Ok(result) => Ok(result),
+        Err(err) => panic!("[call_in_child] Error parsing child output: {}", err),
+    }
+}
+
+fn kill_child(child_pid: Pid) {
+    match kill(child_pid, Signal::SIGKILL) {
+        Ok(_) => {}
+        Err(err) => {
+            if err != Errno::ESRCH {
+                panic!(
+                    "[call_long_running_function] Error sending termination signal: {}",
+                    err
+                );
+            }
+        }
+    }
+    // Reap the killed child
+    _ = waitpid(child_pid, None);
+}
+
+fn limit_memory(memory_limit: Option<u64>) {
+    if let Some(memory_limit) = memory_limit {
+        if let Err(err) = setrlimit(Resource::RLIMIT_AS, memory_limit, memory_limit) {
+            eprintln!("Не удалось ограничить память процесса: {}", err);
+        }
+    }
+}
 
-            Err(err) => panic!("[call_long_running_function] waitpid() failed: {}", err),
-        }
-        let took = time.elapsed();
-        if took > threshold {
-            match kill(child_pid, Signal::SIGKILL) {
-                Ok(_) => {}
-                Err(err) => {
-                    if err != Errno::ESRCH {
-                        panic!(
-                            "[call_long_running_function] Error sending termination signal: {}",
-                            err
-                        );
-                    }
+// Under a memory limit the stderr of the child goes through a pipe to tell
+// a failed allocation from other aborts
+fn stderr_pipe(memory_limit: Option<u64>) -> Option<(OwnedFd, OwnedFd)> {
+    memory_limit?;
+    match pipe() {
+        Ok(fds) => Some(fds),
+        Err(err) => {
+            eprintln!("Не удалось перенаправить поток ошибок процесса: {}", err);
+            None
+        }
+    }
+}
+
+fn redirect_stderr(stderr_pipe: Option<(OwnedFd, OwnedFd)>) {
+    if let Some((reader, writer)) = stderr_pipe {
+        drop(reader);
+        unsafe { nix::libc::dup2(writer.as_raw_fd(), nix::libc::STDERR_FILENO) };
+    }
+}
+
+// Forwards the stderr of the child until it exits, the result tells whether
+// an allocation has failed
+fn read_stderr(stderr_pipe: Option<(OwnedFd, OwnedFd)>) -> Option<std::thread::JoinHandle<bool>> {
+    let (reader, writer) = stderr_pipe?;
+    drop(writer);
+    Some(std::thread::spawn(move || {
+        let mut output = vec![];
+        _ = File::from(reader).read_to_end(&mut output);
+        _ = std::io::stderr().write_all(&output);
+        String::from_utf8_lossy(&output).contains(ALLOCATION_FAILURE_MESSAGE)
+    }))
+}
+
+// Failed allocation aborts the process
+fn memory_outcome(
+    outcome: ProbeOutcome,
+    stderr_reader: Option<std::thread::JoinHandle<bool>>,
+) -> ProbeOutcome {
+    let allocation_failed = stderr_reader.is_some_and(|reader| reader.join().unwrap_or(false));
+    match outcome {
+        ProbeOutcome::Signaled(signal) if allocation_failed && signal == Signal::SIGABRT as i32 => {
+            ProbeOutcome::OutOfMemory
+        }
+        outcome => outcome,
+    }
+}
+
+fn status_outcome(status: WaitStatus) -> Option<ProbeOutcome> {
+    match status {
+        WaitStatus::Exited(_, 0) => Some(ProbeOutcome::Completed),
+
+        WaitStatus::Exited(_, PANIC_EXIT_CODE) => Some(ProbeOutcome::Panicked),
+
+        WaitStatus::Exited(_, code) => Some(ProbeOutcome::Exited(code)),
+
+        WaitStatus::Signaled(_, signal, _) => Some(ProbeOutcome::Signaled(signal as i32)),
+
+        // Still alive, stopped or continued child is still running
+        _status => None,
+    }
+}
+
+fn try_wait(child_pid: Pid) -> Option<ProbeOutcome> {
+    match waitpid(child_pid, Some(WaitPidFlag::WNOHANG)) {
+        Ok(status) => status_outcome(status),
+        Err(err) => panic!("[call_long_running_function] waitpid() failed: {}", err),
+    }
+}
+
+fn wait(child_pid: Pid) -> ProbeOutcome {
+    loop {
+        match waitpid(child_pid, None) {
+            Ok(status) => {
+                if let Some(outcome) = status_outcome(status) {
+                    return outcome;
                 }
             }
-            break;
+            Err(Errno::EINTR) => {}
+            Err(err) => panic!("[call_in_child] waitpid() failed: {}", err),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn pidfd_open(pid: Pid) -> Option<OwnedFd> {
+    let fd = unsafe { nix::libc::syscall(nix::libc::SYS_pidfd_open, pid.as_raw(), 0) };
+    if fd < 0 {
+        None
+    } else {
+        Some(unsafe { OwnedFd::from_raw_fd(fd as RawFd) })
+    }
+}
+
+// Blocks until the child exits or the deadline passes. A pidfd becomes
+// readable when the process exits, without it the child is polled
+fn wait_until(child_pid: Pid, deadline: Instant) -> Option<ProbeOutcome> {
+    #[cfg(target_os = "linux")]
+    if let Some(pidfd) = pidfd_open(child_pid) {
+        loop {
+            if let Some(outcome) = try_wait(child_pid) {
+                return Some(outcome);
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return None;
+            }
+            // poll() takes whole milliseconds, rounded up to not wake too early
+            let timeout = PollTimeout::try_from(remaining.as_micros().div_ceil(1000))
+                .unwrap_or(PollTimeout::MAX);
+            let mut fds = [PollFd::new(pidfd.as_fd(), PollFlags::POLLIN)];
+            match poll(&mut fds, timeout) {
+                Ok(_) | Err(Errno::EINTR) => {}
+                Err(err) => panic!("[call_long_running_function] poll() failed: {}", err),
+            }
         }
     }
 
-    return result;
+    loop {
+        if let Some(outcome) = try_wait(child_pid) {
+            return Some(outcome);
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return None;
+        }
+        std::thread::sleep(FALLBACK_POLL_INTERVAL.min(deadline - now));
+    }
 }
@@ -0,0 +1,268 @@
+use anyhow::{bail, Result};
+use num::ToPrimitive;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use std::fmt;
+use std::str::FromStr;
+
+// Separator of the coordinates in the file names of the grid sizes
+pub const GRID_SEPARATOR: char = 'x';
+
+// Size of an algorithm depending on two parameters, e.g. rows and columns
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
+pub struct GridSize {
+    pub a: usize,
+    pub b: usize,
+}
+
+impl GridSize {
+    pub fn new(a: usize, b: usize) -> Self {
+        Self { a, b }
+    }
+}
+
+impl fmt::Display for GridSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}{}", self.a, GRID_SEPARATOR, self.b)
+    }
+}
+
+// Complexity of a grid size is fitted against the product of its sizes
+impl ToPrimitive for GridSize {
+    fn to_i64(&self) -> Option<i64> {
+        self.a.checked_mul(self.b)?.to_i64()
+    }
+
+    fn to_u64(&self) -> Option<u64> {
+        self.a.checked_mul(self.b)?.to_u64()
+    }
+}
+
+impl FromStr for GridSize {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (a, b) = s.split_once(GRID_SEPARATOR).unwrap_or((s, ""));
+        Ok(Self {
+            a: a.parse()?,
+            b: b.parse()?,
+        })
+    }
+}
+
+// Number a size written by Display stands for, the product for a grid size
+pub(crate) fn size_value(size: &str) -> Option<f64> {
+    if size.contains(GRID_SEPARATOR) {
+        size.parse::<GridSize>().ok()?.to_f64()
+    } else {
+        size.parse::<f64>().ok()
+    }
+}
+
+// Recipe of a sequence of sizes, recorded in the pack description so that
+// the same sizes can be built again
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum SizesSpec {
+    // start, start + step, ... up to end inclusive, the step is positive
+    Linear {
+        start: usize,
+        end: usize,
+        step: usize,
+    },
+    // Amount of log-spaced sizes from start to end inclusive, a single size
+    // requires start and end to be equal
+    Geometric {
+        start: usize,
+        end: usize,
+        amount: usize,
+    },
+    // 2^min_exponent ... 2^max_exponent
+    PowersOfTwo {
+        min_exponent: u32,
+        max_exponent: u32,
+    },
+    // Every mantissa times 10^exponent for every exponent in the range
+    Decades {
+        min_exponent: u32,
+        max_exponent: u32,
+        mantissas: Vec<usize>,
+    },
+    // Every size of the base is moved by a random part of itself up to
+    // spread, which is finite and non-negative
+    Jittered {
+        base: Box<SizesSpec>,
+        spread: f64,
+        seed: u64,
+    },
+    // Every pair of the sizes of the two specs, built with build_grid
+    Grid {
+        a: Box<SizesSpec>,
+        b: Box<SizesSpec>,
+    },
+}
+
+impl SizesSpec {
+    pub fn linear(start: usize, end: usize, step: usize) -> Result<Self> {
+        let spec = Self::Linear { start, end, step };
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    pub fn geometric(start: usize, end: usize, amount: usize) -> Result<Self> {
+        let spec = Self::Geometric { start, end, amount };
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    pub fn powers_of_two(min_exponent: u32, max_exponent: u32) -> Self {
+        Self::PowersOfTwo {
+            min_exponent,
+            max_exponent,
+        }
+    }
+
+    pub fn decades(min_exponent: u32, max_exponent: u32, mantissas: &[usize]) -> Self {
+        Self::Decades {
+            min_exponent,
+            max_exponent,
+            mantissas: mantissas.to_vec(),
+        }
+    }
+
+    // 1, 2, 5, 10, 20, 50...
+    pub fn one_two_five(min_exponent: u32, max_exponent: u32) -> Self {
+        Self::decades(min_exponent, max_exponent, &[1, 2, 5])
+    }
+
+    // Spread is a part of the size, 0.1 moves every size by up to 10%
+    pub fn with_jitter(self, spread: f64, seed: u64) -> Result<Self> {
+        let spec = Self::Jittered {
+            base: Box::new(self),
+            spread,
+            seed,
+        };
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    pub fn grid(a: SizesSpec, b: SizesSpec) -> Self {
+        Self::Grid {
+            a: Box::new(a),
+            b: Box::new(b),
+        }
+    }
+
+    // Checks the parameters the constructors do not let through, e.g. of a
+    // spec read from a description
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            Self::Linear { step: 0, .. } => {
+                bail!("Ошибка: шаг линейной последовательности размеров равен нулю")
+            }
+            Self::Geometric {
+                start,
+                end,
+                amount: 1,
+            } if start != end => bail!(
+                "Ошибка: один размер геометрической последовательности задаётся равными началом и концом"
+            ),
+            Self::Jittered { base, spread, .. } => {
+                if !spread.is_finite() || *spread < 0.0 {
+                    bail!("Ошибка: разброс размеров должен быть конечным неотрицательным числом");
+                }
+                base.validate()
+            }
+            Self::Grid { a, b } => {
+                a.validate()?;
+                b.validate()
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Sorted sizes without repeats. Sizes not representable by T are skipped.
+    // A grid spec is an error, its sizes are built with build_grid
+    pub fn build<T: TryFrom<usize>>(&self) -> Result<Vec<T>> {
+        self.validate()?;
+        let mut sizes = self.sizes()?;
+        sizes.sort_unstable();
+        sizes.dedup();
+        Ok(sizes
+            .into_iter()
+            .filter_map(|size| T::try_from(size).ok())
+            .collect())
+    }
+
+    // Pairs ordered by the first size, then by the second one. A
+    // one-dimensional spec gives the sizes paired with themselves
+    pub fn build_grid(&self) -> Result<Vec<GridSize>> {
+        Ok(match self {
+            Self::Grid { a, b } => {
+                let b_sizes = b.build::<usize>()?;
+                a.build::<usize>()?
+                    .into_iter()
+                    .flat_map(|a| b_sizes.iter().map(move |b| GridSize::new(a, *b)))
+                    .collect()
+            }
+            spec => spec
+                .build::<usize>()?
+                .into_iter()
+                .map(|size| GridSize::new(size, size))
+                .collect(),
+        })
+    }
+
+    // Sizes of a validated spec
+    fn sizes(&self) -> Result<Vec<usize>> {
+        Ok(match self {
+            Self::Linear { start, end, step } => (*start..=*end).step_by(*step).collect(),
+            Self::Geometric { start, end, amount } => {
+                if *amount < 2 {
+                    return Ok(vec![*start; *amount]);
+                }
+                // Logarithmic spacing starts at least from 1
+                let start = (*start).max(1) as f64;
+                let ratio = (*end as f64 / start).powf(1.0 / (*amount - 1) as f64);
+                (0..*amount)
+                    .map(|i| (start * ratio.powi(i as i32)).round() as usize)
+                    .collect()
+            }
+            Self::PowersOfTwo {
+                min_exponent,
+                max_exponent,
+            } => (*min_exponent..=*max_exponent)
+                .filter_map(|exponent| 1usize.checked_shl(exponent))
+                .collect(),
+            Self::Decades {
+                min_exponent,
+                max_exponent,
+                mantissas,
+            } => (*min_exponent..=*max_exponent)
+                .filter_map(|exponent| 10usize.checked_pow(exponent))
+                .flat_map(|power| {
+                    mantissas
+                        .iter()
+                        .filter_map(move |mantissa| mantissa.checked_mul(power))
+                })
+                .collect(),
+            Self::Jittered { base, spread, seed } => {
+                let mut rng = ChaCha8Rng::seed_from_u64(*seed);
+                base.sizes()?
+                    .into_iter()
+                    .map(|size| {
+                        let shift = if *spread > 0.0 {
+                            rng.gen_range(-*spread..=*spread)
+                        } else {
+                            0.0
+                        };
+                        ((size as f64 * (1.0 + shift)).round() as usize).max(1)
+                    })
+                    .collect()
+            }
+            Self::Grid { .. } => bail!("Ошибка: размеры сетки строятся функцией build_grid"),
+        })
+    }
+}
@@ -0,0 +1,352 @@
+use crate::graph::quartiles::Quartiles;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+pub const DEFAULT_BOOTSTRAP_RESAMPLES: usize = 1000;
+pub const DEFAULT_CONFIDENCE_LEVEL: f64 = 0.95;
+// Fixed seed keeps intervals reproducible between print() and graph generation
+const BOOTSTRAP_SEED: u64 = 0x5EED;
+// Above this amount of samples the interval of the mean is taken from the
+// normal distribution, a bootstrap would cost resamples times the samples
+const BOOTSTRAP_SAMPLES_LIMIT: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+pub struct ConfidenceInterval {
+    pub estimate: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl ConfidenceInterval {
+    pub fn half_width(&self) -> f64 {
+        (self.upper - self.lower) / 2.0
+    }
+}
+
+pub fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+pub fn normal_cdf(x: f64) -> f64 {
+    0.5 * erfc(-x / std::f64::consts::SQRT_2)
+}
+
+// Chebyshev approximation from "Numerical Recipes", relative error < 1.2e-7
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let polynomial = -z * z - 1.265_512_23
+        + t * (1.000_023_68
+            + t * (0.374_091_96
+                + t * (0.096_784_18
+                    + t * (-0.186_288_06
+                        + t * (0.278_868_07
+                            + t * (-1.135_203_98
+                                + t * (1.488_515_87 + t * (-0.822_152_23 + t * 0.170_872_77))))))));
+    let result = t * polynomial.exp();
+    if x >= 0.0 {
+        result
+    } else {
+        2.0 - result
+    }
+}
+
+/// Two-sided p-value of the Mann–Whitney U test (normal approximation
+/// with tie and continuity corrections)
+pub fn mann_whitney_u_test(first: &[f64], second: &[f64]) -> f64 {
+    let first_len = first.len() as f64;
+    let second_len = second.len() as f64;
+    if first.is_empty() || second.is_empty() {
+        return 1.0;
+    }
+
+    let mut values = first
+        .iter()
+        .map(|x| (*x, true))
+        .chain(second.iter().map(|x| (*x, false)))
+        .collect::<Vec<(f64, bool)>>();
+    values.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let total_len = values.len();
+    let mut first_ranks_sum = 0.0;
+    let mut ties_correction = 0.0;
+    let mut i = 0;
+    while i < total_len {
+        let mut j = i;
+        while j + 1 < total_len && values[j + 1].0 == values[i].0 {
+            j += 1;
+        }
+        // Tied values share the average of their ranks
+        let rank = (i + j) as f64 / 2.0 + 1.0;
+        let ties = (j - i + 1) as f64;
+        ties_correction += ties * ties * ties - ties;
+        first_ranks_sum += rank * values[i..=j].iter().filter(|x| x.1).count() as f64;
+        i = j + 1;
+    }
+
+    let u = first_ranks_sum - first_len * (first_len + 1.0) / 2.0;
+    let mean_u = first_len * second_len / 2.0;
+    let n = first_len + second_len;
+    let variance_u =
+        first_len * second_len / 12.0 * ((n + 1.0) - ties_correction / (n * (n - 1.0)));
+    if variance_u <= 0.0 {
+        return 1.0;
+    }
+    let z = ((u - mean_u).abs() - 0.5).max(0.0) / variance_u.sqrt();
+
+    (2.0 * (1.0 - normal_cdf(z))).min(1.0)
+}
+
+pub fn median(values: &[f64]) -> f64 {
+    Quartiles::new(values).median()
+}
+
+/// Percentile bootstrap confidence interval of `statistic`
+pub fn bootstrap<F>(
+    samples: &[f64],
+    statistic: F,
+    resamples: usize,
+    confidence_level: f64,
+) -> ConfidenceInterval
+where
+    F: Fn(&[f64]) -> f64,
+{
+    let estimate = statistic(samples);
+    if samples.len() < 2 || resamples == 0 {
+        return ConfidenceInterval {
+            estimate,
+            lower: estimate,
+            upper: estimate,
+        };
+    }
+
+    let mut rng = ChaCha8Rng::seed_from_u64(BOOTSTRAP_SEED);
+    let mut resample = vec![0.0; samples.len()];
+    let mut estimates = Vec::with_capacity(resamples);
+    for _ in 0..resamples {
+        for value in resample.iter_mut() {
+            *value = samples[rng.gen_range(0..samples.len())];
+        }
+        estimates.push(statistic(&resample));
+    }
+    estimates.sort_by(|a, b| a.total_cmp(b));
+
+    let tail_percent = (1.0 - confidence_level) / 2.0 * 100.0;
+    ConfidenceInterval {
+        estimate,
+        lower: Quartiles::quantile(&estimates, tail_percent),
+        upper: Quartiles::quantile(&estimates, 100.0 - tail_percent),
+    }
+}
+
+// Inverse of normal_cdf found by bisection
+pub fn normal_quantile(p: f64) -> f64 {
+    let (mut low, mut high) = (-40.0, 40.0);
+    for _ in 0..100 {
+        let middle = (low + high) / 2.0;
+        if normal_cdf(middle) < p {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    (low + high) / 2.0
+}
+
+/// Confidence interval of the mean by the central limit theorem
+pub fn normal_mean_interval(samples: &[f64], confidence_level: f64) -> ConfidenceInterval {
+    let estimate = mean(samples);
+    if samples.len() < 2 {
+        return ConfidenceInterval {
+            estimate,
+            lower: estimate,
+            upper: estimate,
+        };
+    }
+    let variance =
+        samples.iter().map(|x| (x - estimate).powi(2)).sum::<f64>() / (samples.len() - 1) as f64;
+    let half_width = normal_quantile(1.0 - (1.0 - confidence_level) / 2.0)
+        * (variance / samples.len() as f64).sqrt();
+    ConfidenceInterval {
+        estimate,
+        lower: estimate - half_width,
+        upper: estimate + half_width,
+    }
+}
+
+/// Distribution-free interval of the median between two order statistics:
+/// the amount of samples below the median is binomial with p = 1/2
+pub fn order_statistic_interval(samples: &[f64], confidence_level: f64) -> ConfidenceInterval {
+    if samples.is_empty() {
+        return ConfidenceInterval {
+            estimate: f64::NAN,
+            lower: f64::NAN,
+            upper: f64::NAN,
+        };
+    }
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let n = sorted.len();
+    // Bounds are the rank-th samples from both sides, the largest rank with
+    // the probability of less than rank samples below the median at most
+    // (1 - confidence) / 2. Binomial probabilities are summed from
+    // logarithms, tiny ones underflow to zero
+    let tail_probability = (1.0 - confidence_level) / 2.0;
+    let mut log_probability = n as f64 * 0.5f64.ln();
+    let mut cumulative_probability = 0.0;
+    let mut rank = 0;
+    while rank < n / 2 {
+        cumulative_probability += log_probability.exp();
+        if cumulative_probability > tail_probability {
+            break;
+        }
+        rank += 1;
+        log_probability += ((n - rank + 1) as f64 / rank as f64).ln();
+    }
+    // Too few samples for the confidence level give the whole range
+    let rank = rank.max(1);
+    ConfidenceInterval {
+        estimate: Quartiles::quantile(&sorted, 50.0),
+        lower: sorted[rank - 1],
+        upper: sorted[n - rank],
+    }
+}
+
+pub fn mean_confidence_interval(samples: &[f64]) -> ConfidenceInterval {
+    if samples.len() > BOOTSTRAP_SAMPLES_LIMIT {
+        return normal_mean_interval(samples, DEFAULT_CONFIDENCE_LEVEL);
+    }
+    bootstrap(
+        samples,
+        mean,
+        DEFAULT_BOOTSTRAP_RESAMPLES,
+        DEFAULT_CONFIDENCE_LEVEL,
+    )
+}
+
+pub fn median_confidence_interval(samples: &[f64]) -> ConfidenceInterval {
+    order_statistic_interval(samples, DEFAULT_CONFIDENCE_LEVEL)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum OutlierPolicy {
+    #[default]
+    Keep,
+    Exclude,
+    Winsorize,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct OutlierCounts {
+    pub mild: usize,
+    pub severe: usize,
+}
+
+impl std::fmt::Display for OutlierCounts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.mild, self.severe)
+    }
+}
+
+/// Tukey fences: values outside the inner fences (1.5 IQR) are mild
+/// outliers, values outside the outer fences (3 IQR) are severe ones
+#[derive(Debug, Clone, Copy)]
+pub struct TukeyFences {
+    pub lower_outer: f64,
+    pub lower_inner: f64,
+    pub upper_inner: f64,
+    pub upper_outer: f64,
+}
+
+impl TukeyFences {
+    pub fn new(lower_quartile: f64, upper_quartile: f64) -> Self {
+        let interquartile_range = upper_quartile - lower_quartile;
+        Self {
+            lower_outer: lower_quartile - 3.0 * interquartile_range,
+            lower_inner: lower_quartile - 1.5 * interquartile_range,
+            upper_inner: upper_quartile + 1.5 * interquartile_range,
+            upper_outer: upper_quartile + 3.0 * interquartile_range,
+        }
+    }
+
+    pub fn from_samples(samples: &[f64]) -> Self {
+        let values = Quartiles::new(samples).values();
+        Self::new(values[1], values[3])
+    }
+
+    pub fn classify(&self, samples: &[f64]) -> OutlierCounts {
+        let mut counts = OutlierCounts::default();
+        for value in samples {
+            if *value < self.lower_outer || *value > self.upper_outer {
+                counts.severe += 1;
+            } else if *value < self.lower_inner || *value > self.upper_inner {
+                counts.mild += 1;
+            }
+        }
+        counts
+    }
+}
+
+pub fn classify_outliers(samples: &[f64]) -> OutlierCounts {
+    if samples.is_empty() {
+        return OutlierCounts::default();
+    }
+    TukeyFences::from_samples(samples).classify(samples)
+}
+
+pub fn apply_outlier_policy(samples: &[f64], policy: OutlierPolicy) -> Vec<f64> {
+    if samples.is_empty() || policy == OutlierPolicy::Keep {
+        return samples.to_vec();
+    }
+    let fences = TukeyFences::from_samples(samples);
+    match policy {
+        OutlierPolicy::Keep => samples.to_vec(),
+        OutlierPolicy::Exclude => samples
+            .iter()
+            .filter(|x| **x >= fences.lower_inner && **x <= fences.upper_inner)
+            .copied()
+            .collect(),
+        OutlierPolicy::Winsorize => samples
+            .iter()
+            .map(|x| x.clamp(fences.lower_inner, fences.upper_inner))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mann_whitney_separated_samples() {
+        let p_value = mann_whitney_u_test(&[1.0, 2.0, 3.0, 4.0, 5.0], &[6.0, 7.0, 8.0, 9.0, 10.0]);
+        assert!((p_value - 0.012).abs() < 0.001, "p = {}", p_value);
+    }
+
+    #[test]
+    fn median_interval_by_order_statistics() {
+        let samples = (1..=10).rev().map(f64::from).collect::<Vec<f64>>();
+        let interval = median_confidence_interval(&samples);
+        assert_eq!(interval.estimate, 5.5);
+        assert_eq!((interval.lower, interval.upper), (2.0, 9.0));
+        // Five samples are too few for 95%, the whole range is taken
+        let interval = median_confidence_interval(&[3.0, 1.0, 2.0, 5.0, 4.0]);
+        assert_eq!((interval.lower, interval.upper), (1.0, 5.0));
+    }
+
+    #[test]
+    fn mean_interval_of_many_samples() {
+        let samples = (0..100_000).map(f64::from).collect::<Vec<f64>>();
+        let interval = mean_confidence_interval(&samples);
+        assert_eq!(interval.estimate, 49999.5);
+        assert!((interval.half_width() - 178.9).abs() < 0.1);
+    }
+
+    #[test]
+    fn mann_whitney_identical_samples() {
+        let samples = [1.0, 2.0, 3.0, 4.0, 5.0];
+        assert!((mann_whitney_u_test(&samples, &samples) - 1.0).abs() < 1e-6);
+        assert_eq!(mann_whitney_u_test(&[3.0; 5], &[3.0; 5]), 1.0);
+    }
+}
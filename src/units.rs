@@ -0,0 +1,47 @@
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum TimeUnit {
+    #[default]
+    Nanoseconds,
+    Microseconds,
+    Milliseconds,
+    Seconds,
+}
+
+impl TimeUnit {
+    // Packs written before the unit was recorded in the description
+    pub fn legacy() -> Self {
+        Self::Microseconds
+    }
+
+    pub fn nanoseconds(&self) -> f64 {
+        match self {
+            Self::Nanoseconds => 1.0,
+            Self::Microseconds => 1e3,
+            Self::Milliseconds => 1e6,
+            Self::Seconds => 1e9,
+        }
+    }
+
+    pub fn convert(&self, value: f64, unit: TimeUnit) -> f64 {
+        value * self.nanoseconds() / unit.nanoseconds()
+    }
+
+    pub fn from_duration(&self, duration: Duration) -> f64 {
+        duration.as_nanos() as f64 / self.nanoseconds()
+    }
+}
+
+impl fmt::Display for TimeUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let output = match self {
+            Self::Nanoseconds => "нс",
+            Self::Microseconds => "мкс",
+            Self::Milliseconds => "мс",
+            Self::Seconds => "с",
+        };
+        write!(f, "{}", output)
+    }
+}